@@ -0,0 +1,151 @@
+use bevy::{
+    prelude::*,
+    render::camera::{ScalingMode, Viewport},
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    window::{PrimaryWindow, WindowCreated, WindowResized},
+};
+
+use crate::collision::{Collider, Side};
+use crate::{GameConfig, MainCamera};
+
+/// The playfield, centred on the origin. `width` and `height` are the space
+/// inside the walls.
+#[derive(Resource)]
+pub struct Arena {
+    pub width: f32,
+    pub height: f32,
+    pub wall_thickness: f32,
+}
+
+/// How the arena is laid out. Its size is in the logical units the game is
+/// simulated in, the same whatever the window, and the camera scales it to
+/// fit the window.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ArenaLayout {
+    /// Size inside the walls.
+    pub size: Vec2,
+    /// Walls the ball passes through instead of bouncing off, such as the
+    /// goals behind pong's paddles.
+    pub open_sides: &'static [Side],
+}
+
+/// Sizes the arena and spawns its walls. Setup that places things in the
+/// arena should run after this set.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArenaSetup;
+
+#[derive(Component)]
+pub struct Wall {
+    pub side: Side,
+}
+
+/// The size and centre of the wall along `side`.
+fn wall_placement(arena: &Arena, side: Side) -> (Vec2, Vec2) {
+    let size = match side {
+        Side::Top | Side::Bottom => Vec2::new(arena.width, arena.wall_thickness),
+        Side::Left | Side::Right => Vec2::new(arena.wall_thickness, arena.height),
+    };
+    let offset = Vec2::new(arena.width, arena.height) / 2. + arena.wall_thickness / 2.;
+    (size, side.normal() * offset)
+}
+
+/// The logical area the camera shows: the arena and its walls, with the
+/// margin around them left by the fractions in `GameConfig` for the HUD.
+pub fn view_size(arena: &Arena, config: &GameConfig) -> Vec2 {
+    let walled = Vec2::new(arena.width, arena.height) + arena.wall_thickness * 2.;
+    walled / Vec2::new(config.arena_width_fraction, config.arena_height_fraction)
+}
+
+pub(crate) fn startup(layout: Res<ArenaLayout>, mut arena: ResMut<Arena>) {
+    arena.wall_thickness = 4.;
+    arena.width = layout.size.x;
+    arena.height = layout.size.y;
+}
+
+pub(crate) fn setup_arena(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    arena: Res<Arena>,
+    layout: Res<ArenaLayout>,
+) {
+    for side in [Side::Top, Side::Bottom, Side::Left, Side::Right] {
+        let (size, position) = wall_placement(&arena, side);
+        let mut wall = commands.spawn((
+            Wall { side },
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(Rectangle::new(size.x, size.y))),
+                material: materials.add(Color::WHITE),
+                transform: Transform::from_translation(position.extend(0.)),
+                ..Default::default()
+            },
+        ));
+        if !layout.open_sides.contains(&side) {
+            wall.insert(Collider { size });
+        }
+    }
+}
+
+/// Scales the view up as far as it fits in the window and letterboxes it,
+/// leaving bars on whichever sides are left over. The HUD is scaled with it,
+/// so a pixel in a UI style is a logical unit of the view.
+///
+/// Only refits when the window is created or resized, or the view itself
+/// changes, rather than checking the window every frame.
+pub(crate) fn fit_view_system(
+    mut created: EventReader<WindowCreated>,
+    mut resized: EventReader<WindowResized>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<Arena>,
+    config: Res<GameConfig>,
+    mut cameras: Query<(&mut Camera, &mut OrthographicProjection), With<MainCamera>>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let window_changed = created.read().count() + resized.read().count() > 0;
+    if !window_changed && !arena.is_changed() && !config.is_changed() {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let view = view_size(&arena, &config);
+    let window_size = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    let scale = (window_size / view).min_element();
+    // nothing to draw into while the window is minimised
+    if scale <= 0. {
+        return;
+    }
+    let size = (view * scale).floor().max(Vec2::ONE);
+    let viewport = Viewport {
+        physical_position: ((window_size - size) / 2.).as_uvec2(),
+        physical_size: size.as_uvec2(),
+        ..Default::default()
+    };
+    let hud_scale = scale / window.scale_factor();
+
+    for (mut camera, mut projection) in cameras.iter_mut() {
+        let same_viewport = camera.viewport.as_ref().is_some_and(|current| {
+            current.physical_position == viewport.physical_position
+                && current.physical_size == viewport.physical_size
+        });
+        if !same_viewport {
+            camera.viewport = Some(viewport.clone());
+        }
+        let same_scaling = matches!(
+            projection.scaling_mode,
+            ScalingMode::Fixed { width, height } if width == view.x && height == view.y
+        );
+        if !same_scaling {
+            projection.scaling_mode = ScalingMode::Fixed {
+                width: view.x,
+                height: view.y,
+            };
+        }
+    }
+    if ui_scale.0 != hud_scale {
+        ui_scale.0 = hud_scale;
+    }
+}
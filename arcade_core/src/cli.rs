@@ -0,0 +1,250 @@
+//! Command-line options shared by the game binaries.
+
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::window::{WindowMode, WindowResolution};
+
+use crate::config;
+use crate::{HeadlessPlugin, Replay, ReplayMode, TickLimit};
+
+/// Where matches are recorded to unless told otherwise.
+const DEFAULT_REPLAY_DIR: &str = "replays";
+
+/// The arguments left to parse. Options are taken out as they're read, so
+/// anything left over at the end wasn't recognised.
+pub struct Args {
+    args: Vec<String>,
+}
+
+impl Args {
+    pub fn from_env() -> Self {
+        Args::new(std::env::args().skip(1))
+    }
+
+    pub fn new(args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Args {
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Takes out a switch like `--headless`, returning whether it was given.
+    pub fn flag(&mut self, name: &str) -> bool {
+        let given = self.args.iter().any(|arg| arg == name);
+        self.args.retain(|arg| arg != name);
+        given
+    }
+
+    /// Takes out an option like `--seed 42` and parses its value.
+    pub fn value<T>(&mut self, name: &str) -> Result<Option<T>, String>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let Some(index) = self.args.iter().position(|arg| arg == name) else {
+            return Ok(None);
+        };
+        if index + 1 >= self.args.len() {
+            return Err(format!("{name} needs a value"));
+        }
+        let value = self.args.remove(index + 1);
+        self.args.remove(index);
+        if self.args.iter().any(|arg| arg == name) {
+            return Err(format!("{name} was given more than once"));
+        }
+        value
+            .parse()
+            .map(Some)
+            .map_err(|err| format!("invalid value '{value}' for {name}: {err}"))
+    }
+
+    /// Fails on anything that hasn't been taken out.
+    pub fn finish(self) -> Result<(), String> {
+        match self.args.first() {
+            Some(arg) => Err(format!("unexpected argument '{arg}'")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A window size given as `<width>x<height>`, e.g. `1280x720`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowSize(pub Vec2);
+
+impl FromStr for WindowSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expected = || "expected <width>x<height>, e.g. 1280x720".to_string();
+        let (width, height) = s.split_once('x').ok_or_else(expected)?;
+        let width: f32 = width.parse().map_err(|_| expected())?;
+        let height: f32 = height.parse().map_err(|_| expected())?;
+        if !(width >= 1. && height >= 1.) {
+            return Err("the window must be at least 1x1".to_string());
+        }
+        Ok(WindowSize(Vec2::new(width, height)))
+    }
+}
+
+/// Help for the options in `CommonArgs`, for the games to print under their
+/// own.
+pub const COMMON_USAGE: &str = "  --window <width>x<height>  window size, e.g. 1280x720
+  --fullscreen               fill the screen instead of opening a window
+  --headless                 run without a window, starting a match straight away
+  --ticks <n>                exit once a match has run for n ticks
+  --tick-rate <n>            simulation ticks per second [default: 120]
+  --seed <n>                 seed every match with n instead of a random number
+  --config <file>            config file, relative to the assets folder
+  --controls <file>          file key bindings are loaded from and saved to [default: <game>.controls.ron]
+  --record <dir>             directory matches are recorded to [default: replays]
+  --no-record                don't record matches
+  --replay <file>            play back a recorded match
+  --help                     print this help";
+
+/// The options every game takes.
+#[derive(Debug, Default)]
+pub struct CommonArgs {
+    pub window_size: Option<Vec2>,
+    pub fullscreen: bool,
+    pub headless: bool,
+    pub ticks: Option<u64>,
+    /// Simulation ticks per second, if not the game's own.
+    pub tick_rate: Option<f64>,
+    pub seed: Option<u64>,
+    /// Asset path of the config file.
+    pub config: Option<String>,
+    /// Where the players' bindings are kept. There are none without a
+    /// window.
+    pub controls: Option<PathBuf>,
+    /// Where matches are recorded to, if they are.
+    pub record: Option<PathBuf>,
+    /// A match to play back instead of reading the players' controls.
+    pub replay: Option<Replay>,
+}
+
+impl CommonArgs {
+    /// Takes the shared options out of `args`, loading any replay given,
+    /// which must have been recorded in `game`.
+    pub fn parse(args: &mut Args, game: &str) -> Result<Self, String> {
+        let window_size = args.value::<WindowSize>("--window")?.map(|size| size.0);
+        let fullscreen = args.flag("--fullscreen");
+        let headless = args.flag("--headless");
+        let ticks = args.value::<u64>("--ticks")?;
+        let tick_rate = args.value::<f64>("--tick-rate")?;
+        let seed = args.value::<u64>("--seed")?;
+        let config = args.value::<String>("--config")?;
+        let controls = args.value::<PathBuf>("--controls")?;
+        let record = args.value::<PathBuf>("--record")?;
+        let no_record = args.flag("--no-record");
+        let replay = args.value::<PathBuf>("--replay")?;
+
+        if headless && (window_size.is_some() || fullscreen) {
+            return Err("--window and --fullscreen can't be used with --headless".to_string());
+        }
+        if headless && controls.is_some() {
+            return Err("--controls can't be used with --headless".to_string());
+        }
+        if window_size.is_some() && fullscreen {
+            return Err("--window can't be used with --fullscreen".to_string());
+        }
+        if ticks == Some(0) {
+            return Err("--ticks must be at least 1".to_string());
+        }
+        if tick_rate.is_some_and(|rate| !(rate.is_finite() && rate > 0.)) {
+            return Err("--tick-rate must be above 0".to_string());
+        }
+        if record.is_some() && no_record {
+            return Err("--record can't be used with --no-record".to_string());
+        }
+        if replay.is_some() {
+            let clashing = [
+                ("--seed", seed.is_some()),
+                ("--tick-rate", tick_rate.is_some()),
+                ("--config", config.is_some()),
+                ("--record", record.is_some()),
+                ("--no-record", no_record),
+            ];
+            if let Some((name, _)) = clashing.iter().find(|(_, given)| *given) {
+                return Err(format!(
+                    "{name} can't be used with --replay, which plays back the match as it was recorded"
+                ));
+            }
+        }
+        if let Some(config) = &config {
+            if !config.ends_with(".config.ron") {
+                return Err(format!("config file '{config}' should end in .config.ron"));
+            }
+            let file = config::asset_file(config);
+            if !file.is_file() {
+                return Err(format!("no config file at {}", file.display()));
+            }
+        }
+        let replay = replay
+            .map(|path| {
+                Replay::load(&path, game).map_err(|err| format!("{}: {err}", path.display()))
+            })
+            .transpose()?;
+        let record = match (&replay, no_record) {
+            (None, false) => Some(record.unwrap_or_else(|| DEFAULT_REPLAY_DIR.into())),
+            _ => None,
+        };
+
+        let controls = match headless {
+            false => Some(controls.unwrap_or_else(|| format!("{game}.controls.ron").into())),
+            true => None,
+        };
+
+        Ok(CommonArgs {
+            window_size,
+            fullscreen,
+            headless,
+            ticks,
+            tick_rate,
+            seed,
+            config,
+            controls,
+            record,
+            replay,
+        })
+    }
+
+    /// Whether to record matches or play one back.
+    pub fn replay_mode(&self) -> ReplayMode {
+        match (&self.replay, &self.record) {
+            (Some(replay), _) => ReplayMode::Playback(replay.clone()),
+            (None, Some(dir)) => ReplayMode::Record(dir.clone()),
+            (None, None) => ReplayMode::Off,
+        }
+    }
+
+    /// Starts the app with a window titled `title`, or without one when
+    /// running headless. The game's plugin is added after.
+    pub fn app(&self, title: &str) -> App {
+        let mut app = App::new();
+        if self.headless {
+            app.add_plugins((HeadlessPlugin, LogPlugin::default()));
+        } else {
+            let mut window = Window {
+                title: title.to_string(),
+                ..Default::default()
+            };
+            if let Some(size) = self.window_size {
+                window.resolution = WindowResolution::new(size.x, size.y);
+            }
+            if self.fullscreen {
+                window.mode = WindowMode::BorderlessFullscreen;
+            }
+            app.add_plugins(DefaultPlugins.set(WindowPlugin {
+                primary_window: Some(window),
+                ..Default::default()
+            }));
+        }
+        if let Some(ticks) = self.ticks {
+            app.insert_resource(TickLimit(ticks));
+        }
+        app
+    }
+}
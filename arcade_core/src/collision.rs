@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::Velocity;
+
+/// The face of a box the ball ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Side {
+    /// Unit vector pointing out of the face.
+    pub fn normal(self) -> Vec2 {
+        match self {
+            Side::Left => Vec2::NEG_X,
+            Side::Right => Vec2::X,
+            Side::Top => Vec2::Y,
+            Side::Bottom => Vec2::NEG_Y,
+        }
+    }
+}
+
+/// Something the ball bounces off, as a box of `size` centred on the
+/// entity's translation.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Collider {
+    pub size: Vec2,
+}
+
+/// Where along a sweep a moving box first touches another.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    /// Fraction of the motion covered before touching, from 0 to 1.
+    pub time: f32,
+    /// Unit vector pointing out of the face that was hit.
+    pub normal: Vec2,
+}
+
+/// Sweeps a box with half extents `half_size` from `start` by `motion`
+/// against a still box of `size` at `target`, and returns the first point of
+/// contact.
+///
+/// Boxes that are already overlapping count as touching straight away through
+/// the face with the shallowest overlap. Hits the box is moving away from are
+/// ignored, so a box resting against a face can slide or leave freely.
+pub fn sweep(start: Vec2, half_size: Vec2, motion: Vec2, target: Vec2, size: Vec2) -> Option<Hit> {
+    // grow the target by the moving box and cast its centre as a ray
+    let half = size / 2. + half_size;
+    let offset = start - target;
+    let overlap = half - offset.abs();
+
+    let hit = if overlap.x > 0. && overlap.y > 0. {
+        let normal = if overlap.x < overlap.y {
+            Vec2::new(offset.x.signum(), 0.)
+        } else {
+            Vec2::new(0., offset.y.signum())
+        };
+        Hit { time: 0., normal }
+    } else {
+        let mut entry = f32::NEG_INFINITY;
+        let mut exit = f32::INFINITY;
+        let mut normal = Vec2::ZERO;
+        for (offset, motion, half, axis) in [
+            (offset.x, motion.x, half.x, Vec2::X),
+            (offset.y, motion.y, half.y, Vec2::Y),
+        ] {
+            if motion == 0. {
+                if offset.abs() >= half {
+                    return None;
+                }
+                continue;
+            }
+            let near = (-half * motion.signum() - offset) / motion;
+            let far = (half * motion.signum() - offset) / motion;
+            if near > entry {
+                entry = near;
+                normal = -axis * motion.signum();
+            }
+            exit = exit.min(far);
+        }
+        if entry > exit || !(0. ..=1.).contains(&entry) {
+            return None;
+        }
+        Hit {
+            time: entry,
+            normal,
+        }
+    };
+
+    if motion.dot(hit.normal) < 0. {
+        Some(hit)
+    } else {
+        None
+    }
+}
+
+/// Sends the ball back off a paddle whose hitting face points along
+/// `normal`, at the same speed.
+///
+/// `contact` is where along the face the ball landed, from -1 at one end
+/// through 0 in the middle to 1 at the other, and sets the angle the ball
+/// leaves at, up to `max_angle` radians either side of straight out. A
+/// paddle moving across its face cuts the ball, passing on `spin` of its
+/// velocity, though never past `max_angle`.
+pub fn deflect_off_paddle(
+    velocity: &mut Velocity,
+    contact: f32,
+    normal: Vec2,
+    paddle_velocity: Vec2,
+    max_angle: f32,
+    spin: f32,
+) {
+    let tangent = normal.perp();
+    let aim = Vec2::from_angle(contact.clamp(-1., 1.) * max_angle).rotate(normal);
+    let cut = tangent * paddle_velocity.dot(tangent) * spin;
+    let out = aim * velocity.speed + cut;
+    let angle = out.dot(tangent).atan2(out.dot(normal));
+    velocity.direction = Vec2::from_angle(angle.clamp(-max_angle, max_angle)).rotate(normal);
+}
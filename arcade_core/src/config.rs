@@ -0,0 +1,366 @@
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::{
+    asset::{
+        io::{file::FileAssetReader, Reader},
+        AssetLoader, AsyncReadExt, LoadContext,
+    },
+    prelude::*,
+    sprite::Mesh2dHandle,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{Arena, ArenaLayout, Ball, Collider, GameState, Paddle, Side};
+
+/// Gameplay tuning, loaded from a `.config.ron` file in the game's assets.
+///
+/// Editing the file while the game runs applies the new values straight
+/// away, unless a `ConfigLock` holds them back. A file with a mistake in it
+/// is reported and the values already in use are kept.
+#[derive(Asset, Resource, TypePath, Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GameConfig {
+    pub paddle_width: f32,
+    pub paddle_height: f32,
+    /// Units per second a player moves their paddle.
+    pub paddle_speed: f32,
+    /// Fastest a paddle follows the mouse or a touch, in units per second,
+    /// so it can't jump straight to where the pointer is.
+    pub max_pointer_speed: f32,
+    /// Half the width of the ball's hit box.
+    pub ball_radius: f32,
+    /// Units per second the ball is served at.
+    pub ball_speed: f32,
+    /// Units per second the ball speeds up by each time it's hit with a
+    /// paddle, until the next serve.
+    pub speed_up: f32,
+    /// Fastest the ball gets from being hit with paddles.
+    pub max_ball_speed: f32,
+    /// Angle, in degrees either side of straight out, the ball leaves a
+    /// paddle at when it lands on the very end. Nearer the middle it leaves
+    /// straighter.
+    pub max_bounce_angle: f32,
+    /// Share of a moving paddle's speed passed on to the ball as spin.
+    pub spin: f32,
+    /// Largest angle, in degrees either side of straight at the other
+    /// player, that pong serves the ball at.
+    pub max_serve_angle: f32,
+    /// Fraction of the view's width the arena and its walls take up. The
+    /// rest is a margin for the HUD.
+    pub arena_width_fraction: f32,
+    /// Fraction of the view's height the arena and its walls take up.
+    pub arena_height_fraction: f32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            paddle_width: 10.,
+            paddle_height: 50.,
+            paddle_speed: 200.,
+            max_pointer_speed: 800.,
+            ball_radius: 7.,
+            ball_speed: 200.,
+            speed_up: 10.,
+            max_ball_speed: 400.,
+            max_bounce_angle: 45.,
+            spin: 0.5,
+            max_serve_angle: 30.,
+            arena_width_fraction: 1.,
+            arena_height_fraction: 1.,
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, ConfigError> {
+        let config: GameConfig = ron::de::from_bytes(bytes)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let positive = [
+            ("paddle_width", self.paddle_width),
+            ("paddle_height", self.paddle_height),
+            ("paddle_speed", self.paddle_speed),
+            ("max_pointer_speed", self.max_pointer_speed),
+            ("ball_radius", self.ball_radius),
+            ("ball_speed", self.ball_speed),
+        ];
+        for (field, value) in positive {
+            if !(value.is_finite() && value > 0.) {
+                return Err(ConfigError::Invalid {
+                    field,
+                    value,
+                    expected: "a number above 0",
+                });
+            }
+        }
+        for (field, value) in [("speed_up", self.speed_up), ("spin", self.spin)] {
+            if !(value.is_finite() && value >= 0.) {
+                return Err(ConfigError::Invalid {
+                    field,
+                    value,
+                    expected: "0 or more",
+                });
+            }
+        }
+        if !(self.max_ball_speed.is_finite() && self.max_ball_speed >= self.ball_speed) {
+            return Err(ConfigError::Invalid {
+                field: "max_ball_speed",
+                value: self.max_ball_speed,
+                expected: "ball_speed or more",
+            });
+        }
+        for (field, value) in [
+            ("max_bounce_angle", self.max_bounce_angle),
+            ("max_serve_angle", self.max_serve_angle),
+        ] {
+            if !(0. ..90.).contains(&value) {
+                return Err(ConfigError::Invalid {
+                    field,
+                    value,
+                    expected: "an angle from 0 up to 90 degrees",
+                });
+            }
+        }
+        for (field, value) in [
+            ("arena_width_fraction", self.arena_width_fraction),
+            ("arena_height_fraction", self.arena_height_fraction),
+        ] {
+            if !(value > 0. && value <= 1.) {
+                return Err(ConfigError::Invalid {
+                    field,
+                    value,
+                    expected: "a fraction above 0 and at most 1",
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("could not read config file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse config file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("{field} is {value}, expected {expected}")]
+    Invalid {
+        field: &'static str,
+        value: f32,
+        expected: &'static str,
+    },
+}
+
+#[derive(Default)]
+pub struct GameConfigLoader;
+
+impl AssetLoader for GameConfigLoader {
+    type Asset = GameConfig;
+    type Settings = ();
+    type Error = ConfigError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            GameConfig::from_ron(&bytes)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["config.ron"]
+    }
+}
+
+/// Keeps changes to the config file out of matches that have to play out
+/// the same somewhere else.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigLock {
+    /// Holds changes back until the match is over, for a match being
+    /// recorded to play back with the tuning it started with.
+    Match,
+    /// Ignores changes altogether, for a replay playing back or an online
+    /// session, where the peer never sees this file.
+    Session,
+}
+
+#[derive(Resource)]
+struct ConfigFile {
+    path: String,
+    handle: Handle<GameConfig>,
+    /// A change held back until the match is over.
+    pending: Option<GameConfig>,
+}
+
+fn load_config(asset_server: Res<AssetServer>, mut file: ResMut<ConfigFile>) {
+    file.handle = asset_server.load(file.path.clone());
+}
+
+/// Copies the file's values into the `GameConfig` resource whenever it
+/// changes, unless a `ConfigLock` keeps them out.
+fn apply_config_system(
+    mut events: EventReader<AssetEvent<GameConfig>>,
+    mut file: ResMut<ConfigFile>,
+    configs: Res<Assets<GameConfig>>,
+    mut config: ResMut<GameConfig>,
+    lock: Option<Res<ConfigLock>>,
+    state: Res<State<GameState>>,
+) {
+    let in_match = matches!(
+        state.get(),
+        GameState::Countdown | GameState::Playing | GameState::Paused
+    );
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        if *id != file.handle.id() {
+            continue;
+        }
+        let Some(loaded) = configs.get(*id) else {
+            continue;
+        };
+        match lock.as_deref() {
+            Some(ConfigLock::Session) => {
+                warn!("{} changed, but can't be applied here", file.path);
+            }
+            Some(ConfigLock::Match) if in_match => {
+                info!("applying {} once the match is over", file.path);
+                file.pending = Some(*loaded);
+            }
+            _ => {
+                info!("applying {}", file.path);
+                file.pending = None;
+                config.set_if_neq(*loaded);
+            }
+        }
+    }
+    if !in_match {
+        if let Some(pending) = file.pending.take() {
+            info!("applying {}", file.path);
+            config.set_if_neq(pending);
+        }
+    }
+}
+
+/// Resizes the paddles and balls already in play to match the config, and
+/// moves any that no longer fit back inside the walls. Speeds are read every
+/// tick, so they need nothing doing.
+fn resize_system(
+    config: Res<GameConfig>,
+    arena: Res<Arena>,
+    layout: Res<ArenaLayout>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut paddles: Query<
+        (&mut Collider, &mut Mesh2dHandle, &mut Transform),
+        (With<Paddle>, Without<Ball>),
+    >,
+    mut balls: Query<(&mut Mesh2dHandle, &mut Transform), With<Ball>>,
+) {
+    for (mut collider, mut mesh, mut transform) in paddles.iter_mut() {
+        // keep each paddle lying the way it was spawned, thin side across
+        let size = if collider.size.x <= collider.size.y {
+            Vec2::new(config.paddle_width, config.paddle_height)
+        } else {
+            Vec2::new(config.paddle_height, config.paddle_width)
+        };
+        if collider.size != size {
+            collider.size = size;
+            mesh.0 = meshes.add(Rectangle::from_size(size));
+        }
+        keep_inside(&mut transform, size, &arena, &[]);
+    }
+    let ball = meshes.add(Rectangle::from_size(Vec2::splat(config.ball_radius)));
+    for (mut mesh, mut transform) in balls.iter_mut() {
+        mesh.0 = ball.clone();
+        // a ball already through an open side is on its way out, so leave it
+        keep_inside(
+            &mut transform,
+            Vec2::splat(config.ball_radius * 2.),
+            &arena,
+            layout.open_sides,
+        );
+    }
+}
+
+/// Moves something of `size` the least it takes to be clear of the walls,
+/// other than those on the `open` sides.
+fn keep_inside(transform: &mut Transform, size: Vec2, arena: &Arena, open: &[Side]) {
+    let reach = ((Vec2::new(arena.width, arena.height) - size) / 2.).max(Vec2::ZERO);
+    let (mut min, mut max) = (-reach, reach);
+    for side in open {
+        match side {
+            Side::Left => min.x = f32::NEG_INFINITY,
+            Side::Right => max.x = f32::INFINITY,
+            Side::Bottom => min.y = f32::NEG_INFINITY,
+            Side::Top => max.y = f32::INFINITY,
+        }
+    }
+    let position = transform.translation.truncate().clamp(min, max);
+    if position != transform.translation.truncate() {
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+/// Loads `path` into `GameConfig` and keeps it up to date. Added by
+/// `ArcadePlugin`.
+pub(crate) struct ConfigPlugin {
+    pub path: String,
+}
+
+/// Where the asset server reads `asset_path` from on disk.
+pub fn asset_file(asset_path: &str) -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join("assets")
+        .join(asset_path)
+}
+
+impl ConfigPlugin {
+    /// Reads the file straight off the disk, so the arena is laid out with
+    /// its values rather than the built-in ones. Later changes come in
+    /// through the asset server.
+    fn read_now(&self) -> Option<GameConfig> {
+        let path = asset_file(&self.path);
+        let loaded = fs::read(&path)
+            .map_err(ConfigError::from)
+            .and_then(|bytes| GameConfig::from_ron(&bytes));
+        match loaded {
+            Ok(config) => Some(config),
+            Err(err) => {
+                warn!("{}: {err}, using built-in values", path.display());
+                None
+            }
+        }
+    }
+}
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(config) = self.read_now() {
+            app.insert_resource(config);
+        }
+        app.init_asset::<GameConfig>();
+        app.init_asset_loader::<GameConfigLoader>();
+        app.insert_resource(ConfigFile {
+            path: self.path.clone(),
+            handle: Handle::default(),
+            pending: None,
+        });
+        app.add_systems(Startup, load_config);
+        app.add_systems(PreUpdate, apply_config_system);
+        app.add_systems(Update, resize_system.run_if(resource_changed::<GameConfig>));
+    }
+}
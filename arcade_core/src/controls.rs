@@ -0,0 +1,643 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use bevy::ecs::system::SystemParam;
+use bevy::input::touch::Touch;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::hud::PlayerCount;
+use crate::input::PaddleInput;
+use crate::state::{self, spawn_menu_sized, GameState, MenuAction, MenuItem, MenuPicked};
+use crate::MainCamera;
+
+/// How far a stick has to be pushed to pick it while rebinding.
+const CAPTURE_THRESHOLD: f32 = 0.5;
+const ROW_FONT_SIZE: f32 = 18.;
+
+/// Something a player does with their controls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    Serve,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 4] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Serve,
+        Action::Pause,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::Serve => "Serve",
+            Action::Pause => "Pause",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Player {
+    One,
+    Two,
+}
+
+/// A key, a gamepad button, or pushing a gamepad stick one way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Button(GamepadButtonType),
+    /// Pushing a stick along `axis`, towards its positive end or not. The
+    /// further it's pushed, the harder the action is held.
+    Axis {
+        axis: GamepadAxisType,
+        positive: bool,
+    },
+}
+
+impl Binding {
+    fn same_kind(self, other: Binding) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                let name = ["Key", "Digit", "Arrow"]
+                    .iter()
+                    .find_map(|prefix| name.strip_prefix(prefix))
+                    .unwrap_or(&name);
+                f.write_str(name)
+            }
+            Binding::Button(button) => write!(f, "Pad {button:?}"),
+            Binding::Axis { axis, positive } => {
+                write!(f, "{axis:?}{}", if *positive { "+" } else { "-" })
+            }
+        }
+    }
+}
+
+/// What one player's actions are bound to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerBindings {
+    /// Which gamepad is this player's, counting the connected ones from 0.
+    pub gamepad: usize,
+    pub move_up: Vec<Binding>,
+    pub move_down: Vec<Binding>,
+    pub serve: Vec<Binding>,
+    pub pause: Vec<Binding>,
+    /// Whether the paddle follows the mouse cursor, instead of the bindings
+    /// while it's over the window. Touches steer it either way.
+    #[serde(default)]
+    pub pointer: bool,
+}
+
+impl PlayerBindings {
+    /// Moves with the `up` and `down` keys, and with the gamepad's left stick
+    /// and d-pad along `axis`, either `LeftStickX` or `LeftStickY`. Serves
+    /// with the `serve` key or the gamepad's south button, and pauses with
+    /// escape or start.
+    pub fn new(
+        gamepad: usize,
+        up: KeyCode,
+        down: KeyCode,
+        serve: KeyCode,
+        axis: GamepadAxisType,
+    ) -> Self {
+        let (pad_up, pad_down) = match axis {
+            GamepadAxisType::LeftStickX | GamepadAxisType::RightStickX => {
+                (GamepadButtonType::DPadRight, GamepadButtonType::DPadLeft)
+            }
+            _ => (GamepadButtonType::DPadUp, GamepadButtonType::DPadDown),
+        };
+        PlayerBindings {
+            gamepad,
+            move_up: vec![
+                Binding::Key(up),
+                Binding::Button(pad_up),
+                Binding::Axis {
+                    axis,
+                    positive: true,
+                },
+            ],
+            move_down: vec![
+                Binding::Key(down),
+                Binding::Button(pad_down),
+                Binding::Axis {
+                    axis,
+                    positive: false,
+                },
+            ],
+            serve: vec![
+                Binding::Key(serve),
+                Binding::Button(GamepadButtonType::South),
+            ],
+            pause: vec![
+                Binding::Key(KeyCode::Escape),
+                Binding::Button(GamepadButtonType::Start),
+            ],
+            pointer: false,
+        }
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        match action {
+            Action::MoveUp => &self.move_up,
+            Action::MoveDown => &self.move_down,
+            Action::Serve => &self.serve,
+            Action::Pause => &self.pause,
+        }
+    }
+
+    pub fn get_mut(&mut self, action: Action) -> &mut Vec<Binding> {
+        match action {
+            Action::MoveUp => &mut self.move_up,
+            Action::MoveDown => &mut self.move_down,
+            Action::Serve => &mut self.serve,
+            Action::Pause => &mut self.pause,
+        }
+    }
+
+    /// Binds `action` to `binding` in place of whatever of the same kind it
+    /// was bound to, so a new key replaces the old key and leaves the
+    /// gamepad alone.
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.get_mut(action);
+        bindings.retain(|bound| !bound.same_kind(binding));
+        bindings.push(binding);
+    }
+}
+
+/// Which keys, buttons and sticks each player's actions are bound to. Saved
+/// to the controls file whenever it's changed from the controls menu.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputMap {
+    pub player1: PlayerBindings,
+    pub player2: PlayerBindings,
+}
+
+impl Default for InputMap {
+    /// Two people sharing a keyboard: W/S for player 1 and the arrow keys
+    /// for player 2, each serving by pressing towards the other. Each has a
+    /// gamepad of their own too.
+    fn default() -> Self {
+        InputMap {
+            player1: PlayerBindings::new(
+                0,
+                KeyCode::KeyW,
+                KeyCode::KeyS,
+                KeyCode::KeyD,
+                GamepadAxisType::LeftStickY,
+            ),
+            player2: PlayerBindings::new(
+                1,
+                KeyCode::ArrowUp,
+                KeyCode::ArrowDown,
+                KeyCode::ArrowLeft,
+                GamepadAxisType::LeftStickY,
+            ),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn get(&self, player: Player) -> &PlayerBindings {
+        match player {
+            Player::One => &self.player1,
+            Player::Two => &self.player2,
+        }
+    }
+
+    pub fn get_mut(&mut self, player: Player) -> &mut PlayerBindings {
+        match player {
+            Player::One => &mut self.player1,
+            Player::Two => &mut self.player2,
+        }
+    }
+
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, ControlsError> {
+        Ok(ron::de::from_bytes(bytes)?)
+    }
+
+    pub fn to_ron(&self) -> Result<String, ControlsError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ControlsError {
+    #[error("could not read controls file: {0}")]
+    Io(#[from] io::Error),
+    #[error("could not parse controls file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("could not write controls file: {0}")]
+    Write(#[from] ron::Error),
+}
+
+/// Where the mouse cursor and each touch are in the world, seen through
+/// `MainCamera`.
+#[derive(Resource, Debug, Default)]
+pub struct Pointers {
+    /// Over the window, if it is.
+    pub cursor: Option<Vec2>,
+    /// In the order the fingers went down.
+    pub touches: Vec<Vec2>,
+}
+
+fn track_pointers_system(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    touches: Res<Touches>,
+    mut pointers: ResMut<Pointers>,
+) {
+    pointers.cursor = None;
+    pointers.touches.clear();
+    let (Ok(window), Ok((camera, transform))) = (windows.get_single(), cameras.get_single()) else {
+        return;
+    };
+    // window positions are from the corner of the window, not the letterbox
+    let corner = camera
+        .logical_viewport_rect()
+        .map_or(Vec2::ZERO, |viewport| viewport.min);
+    let to_world = |position: Vec2| camera.viewport_to_world_2d(transform, position - corner);
+    pointers.cursor = window.cursor_position().and_then(to_world);
+    let mut touching: Vec<&Touch> = touches.iter().collect();
+    touching.sort_by_key(|touch| touch.id());
+    pointers.touches = touching
+        .into_iter()
+        .filter_map(|touch| to_world(touch.position()))
+        .collect();
+}
+
+/// Reads the players' actions through the `InputMap`.
+#[derive(SystemParam)]
+pub struct PlayerControls<'w> {
+    map: Res<'w, InputMap>,
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    gamepads: Res<'w, Gamepads>,
+    buttons: Res<'w, ButtonInput<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    pointers: Res<'w, Pointers>,
+}
+
+impl PlayerControls<'_> {
+    /// The player's gamepad, if that many are connected.
+    fn gamepad(&self, player: Player) -> Option<Gamepad> {
+        let mut gamepads: Vec<Gamepad> = self.gamepads.iter().collect();
+        gamepads.sort_by_key(|gamepad| gamepad.id);
+        gamepads.get(self.map.get(player).gamepad).copied()
+    }
+
+    /// How hard `player` is holding `action`, from 0 to 1.
+    pub fn strength(&self, player: Player, action: Action) -> f32 {
+        let gamepad = self.gamepad(player);
+        let held = |binding: &Binding| match *binding {
+            Binding::Key(key) => self.keys.pressed(key) as u8 as f32,
+            Binding::Button(button) => gamepad.map_or(0., |gamepad| {
+                self.buttons.pressed(GamepadButton::new(gamepad, button)) as u8 as f32
+            }),
+            Binding::Axis { axis, positive } => gamepad
+                .and_then(|gamepad| self.axes.get(GamepadAxis::new(gamepad, axis)))
+                .map_or(0., |value| {
+                    let value = if positive { value } else { -value };
+                    value.clamp(0., 1.)
+                }),
+        };
+        self.map
+            .get(player)
+            .get(action)
+            .iter()
+            .map(held)
+            .fold(0., f32::max)
+    }
+
+    /// Whether `player` started holding `action` this frame. Sticks don't
+    /// count, having no moment they're pressed.
+    pub fn just_pressed(&self, player: Player, action: Action) -> bool {
+        let gamepad = self.gamepad(player);
+        self.map
+            .get(player)
+            .get(action)
+            .iter()
+            .any(|binding| match *binding {
+                Binding::Key(key) => self.keys.just_pressed(key),
+                Binding::Button(button) => gamepad.is_some_and(|gamepad| {
+                    self.buttons
+                        .just_pressed(GamepadButton::new(gamepad, button))
+                }),
+                Binding::Axis { .. } => false,
+            })
+    }
+
+    /// What `player` is doing with their paddle. A stick part way over moves
+    /// the paddle at part speed.
+    ///
+    /// A touch, or the cursor if the player follows it, steers the paddle
+    /// instead. `steer` gives where along its way the paddle should go for a
+    /// point in the world, or `None` for a point that isn't this player's,
+    /// such as on the other player's side.
+    pub fn paddle_input(&self, player: Player, steer: impl Fn(Vec2) -> Option<f32>) -> PaddleInput {
+        let up = self.strength(player, Action::MoveUp);
+        let down = self.strength(player, Action::MoveDown);
+        let cursor = self
+            .pointers
+            .cursor
+            .filter(|_| self.map.get(player).pointer);
+        let target = self
+            .pointers
+            .touches
+            .iter()
+            .copied()
+            .chain(cursor)
+            .find_map(steer);
+        PaddleInput {
+            serve: self.strength(player, Action::Serve) > 0.,
+            ..target.map_or(PaddleInput::moving(up - down), PaddleInput::towards)
+        }
+    }
+}
+
+/// The bindings the game starts with, for resetting to from the menu.
+#[derive(Resource)]
+struct DefaultInputMap(InputMap);
+
+/// Where the input map is loaded from and saved to, if anywhere, and what's
+/// in it.
+#[derive(Resource)]
+struct ControlsFile {
+    path: Option<PathBuf>,
+    saved: InputMap,
+}
+
+/// The action waiting for a new key, button or stick to be pressed.
+#[derive(Resource)]
+pub(crate) struct Rebinding {
+    player: Player,
+    action: Action,
+}
+
+/// The players in the game, who get rows in the controls menu.
+pub(crate) fn players(count: &PlayerCount) -> &'static [Player] {
+    if count.0 > 1 {
+        &[Player::One, Player::Two]
+    } else {
+        &[Player::One]
+    }
+}
+
+fn setup_controls_menu(mut commands: Commands, count: Res<PlayerCount>) {
+    let mut rows = Vec::new();
+    for &player in players(&count) {
+        for action in Action::ALL {
+            rows.push((String::new(), MenuAction::Rebind(player, action)));
+        }
+        rows.push((String::new(), MenuAction::TogglePointer(player)));
+    }
+    rows.push(("Reset to defaults".to_string(), MenuAction::ResetControls));
+    rows.push(("Back".to_string(), MenuAction::MainMenu));
+    let items: Vec<(&str, MenuAction)> = rows
+        .iter()
+        .map(|(label, action)| (label.as_str(), *action))
+        .collect();
+    spawn_menu_sized(&mut commands, "Controls", None, &items, ROW_FONT_SIZE);
+}
+
+/// Starts rebinding the picked action, switches following the mouse, or puts
+/// every binding back.
+fn controls_picked_system(
+    mut commands: Commands,
+    mut picked: EventReader<MenuPicked>,
+    mut map: ResMut<InputMap>,
+    defaults: Res<DefaultInputMap>,
+) {
+    for MenuPicked(action) in picked.read() {
+        match *action {
+            MenuAction::Rebind(player, action) => {
+                commands.insert_resource(Rebinding { player, action });
+            }
+            MenuAction::TogglePointer(player) => {
+                let bindings = map.get_mut(player);
+                bindings.pointer = !bindings.pointer;
+            }
+            MenuAction::ResetControls => *map = defaults.0.clone(),
+            _ => {}
+        }
+    }
+}
+
+/// Binds the action being rebound to whatever is pressed next. A gamepad's
+/// button or stick also makes that gamepad the player's. Escape cancels and
+/// backspace clears the action's bindings.
+fn capture_binding_system(
+    mut commands: Commands,
+    rebinding: Res<Rebinding>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut map: ResMut<InputMap>,
+) {
+    let bindings = map.get_mut(rebinding.player);
+    if keys.just_pressed(KeyCode::Escape) {
+        commands.remove_resource::<Rebinding>();
+        return;
+    }
+    if keys.just_pressed(KeyCode::Backspace) {
+        bindings.get_mut(rebinding.action).clear();
+        commands.remove_resource::<Rebinding>();
+        return;
+    }
+
+    let pressed = keys
+        .get_just_pressed()
+        .next()
+        .map(|&key| (Binding::Key(key), None))
+        .or_else(|| {
+            buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| (Binding::Button(button.button_type), Some(button.gamepad)))
+        })
+        .or_else(|| {
+            gamepads.iter().find_map(|gamepad| {
+                [
+                    GamepadAxisType::LeftStickX,
+                    GamepadAxisType::LeftStickY,
+                    GamepadAxisType::RightStickX,
+                    GamepadAxisType::RightStickY,
+                ]
+                .into_iter()
+                .find_map(|axis| {
+                    let value = axes.get(GamepadAxis::new(gamepad, axis))?;
+                    (value.abs() >= CAPTURE_THRESHOLD).then_some((
+                        Binding::Axis {
+                            axis,
+                            positive: value > 0.,
+                        },
+                        Some(gamepad),
+                    ))
+                })
+            })
+        });
+    let Some((binding, gamepad)) = pressed else {
+        return;
+    };
+    if let Some(gamepad) = gamepad {
+        let mut connected: Vec<Gamepad> = gamepads.iter().collect();
+        connected.sort_by_key(|gamepad| gamepad.id);
+        if let Some(index) = connected.iter().position(|&g| g == gamepad) {
+            bindings.gamepad = index;
+        }
+    }
+    bindings.rebind(rebinding.action, binding);
+    commands.remove_resource::<Rebinding>();
+}
+
+/// A row's name, saying whose it is when there's more than one player.
+fn row_name(count: &PlayerCount, player: Player, label: &str) -> String {
+    match (count.0 > 1, player) {
+        (false, _) => label.to_string(),
+        (true, Player::One) => format!("P1 {label}"),
+        (true, Player::Two) => format!("P2 {label}"),
+    }
+}
+
+/// Shows each action's bindings on its row of the controls menu.
+fn controls_text_system(
+    map: Res<InputMap>,
+    count: Res<PlayerCount>,
+    rebinding: Option<Res<Rebinding>>,
+    mut items: Query<(&MenuItem, &mut Text)>,
+) {
+    for (item, mut text) in items.iter_mut() {
+        let (player, action) = match item.action {
+            MenuAction::Rebind(player, action) => (player, action),
+            MenuAction::TogglePointer(player) => {
+                let name = row_name(&count, player, "Follow mouse");
+                let on = if map.get(player).pointer { "on" } else { "off" };
+                let value = format!("{name}: {on}");
+                if text.sections[0].value != value {
+                    text.sections[0].value = value;
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let name = row_name(&count, player, action.label());
+        let waiting = rebinding
+            .as_ref()
+            .is_some_and(|r| r.player == player && r.action == action);
+        let value = if waiting {
+            format!("{name}: press a key, button or stick (Esc to cancel)")
+        } else {
+            let bound: Vec<String> = map
+                .get(player)
+                .get(action)
+                .iter()
+                .map(Binding::to_string)
+                .collect();
+            format!("{name}: {}", bound.join(", "))
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+/// Saves the bindings on leaving the controls menu, if they were changed.
+fn save_controls(mut commands: Commands, map: Res<InputMap>, mut file: ResMut<ControlsFile>) {
+    commands.remove_resource::<Rebinding>();
+    if file.saved == *map {
+        return;
+    }
+    file.saved = map.clone();
+    let Some(path) = &file.path else {
+        return;
+    };
+    let saved = map.to_ron().and_then(|ron| Ok(fs::write(path, ron)?));
+    match saved {
+        Ok(()) => info!("saved controls to {}", path.display()),
+        Err(err) => error!("could not save controls to {}: {err}", path.display()),
+    }
+}
+
+/// Reads the players' controls through an `InputMap`, tracks the pointers
+/// that can steer paddles, and adds the menu for changing the bindings.
+/// Added by `ArcadePlugin`.
+pub(crate) struct ControlsPlugin {
+    /// Bindings used when there's no controls file.
+    pub defaults: InputMap,
+    /// File the bindings are loaded from and saved to.
+    pub path: Option<PathBuf>,
+}
+
+impl ControlsPlugin {
+    /// The bindings saved in the file, or the defaults if there's no file
+    /// or it can't be read.
+    fn load(&self) -> InputMap {
+        let Some(path) = &self.path else {
+            return self.defaults.clone();
+        };
+        let loaded = match fs::read(path) {
+            Ok(bytes) => InputMap::from_ron(&bytes),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return self.defaults.clone();
+            }
+            Err(err) => Err(err.into()),
+        };
+        loaded.unwrap_or_else(|err| {
+            warn!("{}: {err}, using the default controls", path.display());
+            self.defaults.clone()
+        })
+    }
+}
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        let map = self.load();
+        app.insert_resource(ControlsFile {
+            path: self.path.clone(),
+            saved: map.clone(),
+        });
+        app.insert_resource(map);
+        app.insert_resource(DefaultInputMap(self.defaults.clone()));
+        app.init_resource::<Pointers>();
+        app.add_systems(PreUpdate, track_pointers_system.after(InputSystem));
+        app.add_systems(OnEnter(GameState::Controls), setup_controls_menu);
+        app.add_systems(OnExit(GameState::Controls), save_controls);
+        app.add_systems(
+            Update,
+            (
+                // after the menu, which sits out the frame the binding is
+                // captured in rather than acting on the same press, and
+                // before the pick that starts rebinding, for the same reason
+                capture_binding_system
+                    .run_if(resource_exists::<Rebinding>)
+                    .after(state::menu_input_system)
+                    .before(controls_picked_system),
+                controls_picked_system.after(state::menu_input_system),
+                controls_text_system,
+            )
+                .run_if(in_state(GameState::Controls)),
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! What happens in play, for sound, effects and stats to react to without
+//! reaching into the rules or physics.
+//!
+//! Each is sent from `FixedUpdate` on the tick it happens, through
+//! `GameEvents`. Online, a tick that's rolled back and simulated again
+//! doesn't send its events a second time, though the ones it sent the first
+//! time, guessing at the peer's input, stand.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::{Player, Side};
+
+/// Present while ticks that were already simulated once are simulated
+/// again, such as after an online rollback.
+#[derive(Resource, Debug)]
+pub struct Resimulating;
+
+/// Sends gameplay events, except while `Resimulating`, so whatever reacts to
+/// them hears about each one once.
+#[derive(SystemParam)]
+pub struct GameEvents<'w, E: Event> {
+    writer: EventWriter<'w, E>,
+    resimulating: Option<Res<'w, Resimulating>>,
+}
+
+impl<E: Event> GameEvents<'_, E> {
+    pub fn send(&mut self, event: E) {
+        if self.resimulating.is_none() {
+            self.writer.send(event);
+        }
+    }
+}
+
+/// A ball bounced off a paddle. Sent by the physics step.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct BallHitPaddle {
+    pub ball: Entity,
+    pub paddle: Entity,
+    /// Where the ball's centre was when it touched.
+    pub point: Vec2,
+    /// Unit vector pointing out of the face that was hit.
+    pub normal: Vec2,
+    /// The ball's speed coming off the paddle, sped up by the hit.
+    pub speed: f32,
+}
+
+/// A ball bounced off one of the arena's walls. Sent by the physics step.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct BallHitWall {
+    pub ball: Entity,
+    pub wall: Entity,
+    pub side: Side,
+    /// Where the ball's centre was when it touched.
+    pub point: Vec2,
+    pub speed: f32,
+}
+
+/// A ball went out through an open side of the arena.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct GoalScored {
+    pub ball: Entity,
+    /// Who won the point, or `None` when nobody does, as when the ball gets
+    /// past breakout's paddle.
+    pub scorer: Option<Player>,
+    /// The side it went out through.
+    pub side: Side,
+    /// Where the ball's centre was when it went out.
+    pub point: Vec2,
+    pub speed: f32,
+}
+
+/// A ball broke a brick.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct BrickDestroyed {
+    pub ball: Entity,
+    /// Already despawned by the time this is read.
+    pub brick: Entity,
+    /// Where the ball's centre was when it touched.
+    pub point: Vec2,
+    pub speed: f32,
+    /// Points scored for it.
+    pub points: usize,
+}
+
+/// A ball was put into play.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ServeStarted {
+    pub ball: Entity,
+    pub server: Player,
+    /// Where the ball was served from.
+    pub point: Vec2,
+    pub speed: f32,
+}
+
+/// The match or game is over.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchEnded {
+    /// `None` for a game played alone, which ends with nobody winning.
+    pub winner: Option<Player>,
+}
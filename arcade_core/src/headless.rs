@@ -0,0 +1,38 @@
+use bevy::app::AppExit;
+use bevy::asset::AssetPlugin;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+
+use crate::replay::ReplayPlayback;
+use crate::GameState;
+
+/// Runs a game without a window or GPU, in place of `DefaultPlugins`. Adds
+/// the asset and input plugins the games rely on to `MinimalPlugins`, and
+/// registers the mesh and material assets they spawn with.
+///
+/// There are no menus to click through, so a match starts straight away and
+/// the app exits once it's over, or once a replay runs out of inputs.
+pub struct HeadlessPlugin;
+
+fn skip_main_menu(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Countdown);
+}
+
+fn exit_when_over(mut exit: EventWriter<AppExit>) {
+    exit.send(AppExit);
+}
+
+fn replay_finished(playback: Option<Res<ReplayPlayback>>) -> bool {
+    playback.is_some_and(|playback| playback.finished())
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), InputPlugin));
+        app.init_asset::<Mesh>();
+        app.init_asset::<ColorMaterial>();
+        app.add_systems(Startup, skip_main_menu);
+        app.add_systems(OnEnter(GameState::GameOver), exit_when_over);
+        app.add_systems(Update, exit_when_over.run_if(replay_finished));
+    }
+}
@@ -0,0 +1,278 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::GameConfig;
+#[cfg(debug_assertions)]
+use crate::{Ball, Velocity};
+
+/// Points for each player. Single-player games only use `player1`.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Score {
+    pub player1: usize,
+    pub player2: usize,
+}
+
+#[derive(Component)]
+pub struct Player1;
+
+#[derive(Component)]
+pub struct Player2;
+
+#[derive(Component)]
+pub struct ScoreText;
+
+/// Where a piece of the HUD goes, around or over the arena. Each region is a
+/// flexbox node that lines up whatever is spawned into it, and stays lined up
+/// with the arena at any window size.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HudRegion {
+    /// Above the arena, against its left edge.
+    TopLeft,
+    /// Above the middle of the arena.
+    Top,
+    /// Above the arena, against its right edge.
+    TopRight,
+    /// Over the middle of the arena, for messages.
+    Centre,
+    /// Below the arena, against its left edge.
+    BottomLeft,
+    /// Below the middle of the arena.
+    Bottom,
+    /// Below the arena, against its right edge.
+    BottomRight,
+}
+
+impl HudRegion {
+    /// How the region lines up its contents. The ones on the left and right
+    /// fill in from the arena's edge inwards.
+    fn style(self) -> Style {
+        let (direction, justify) = match self {
+            HudRegion::TopLeft | HudRegion::BottomLeft => {
+                (FlexDirection::Row, JustifyContent::FlexStart)
+            }
+            HudRegion::TopRight | HudRegion::BottomRight => {
+                (FlexDirection::RowReverse, JustifyContent::FlexStart)
+            }
+            HudRegion::Top | HudRegion::Centre | HudRegion::Bottom => {
+                (FlexDirection::Row, JustifyContent::Center)
+            }
+        };
+        Style {
+            flex_direction: direction,
+            justify_content: justify,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(16.),
+            flex_grow: 1.,
+            flex_basis: Val::Px(0.),
+            ..Default::default()
+        }
+    }
+}
+
+/// The HUD's regions, for games to spawn their text into.
+#[derive(Resource)]
+pub struct Hud {
+    regions: HashMap<HudRegion, Entity>,
+}
+
+impl Hud {
+    /// The node to spawn children into for `region`.
+    pub fn region(&self, region: HudRegion) -> Entity {
+        self.regions[&region]
+    }
+}
+
+/// The node covering the whole view that the HUD's bands sit in.
+#[derive(Component)]
+pub(crate) struct HudRoot;
+
+/// A row of regions: the one over the arena, or one of the margins above and
+/// below it.
+#[derive(Component)]
+pub(crate) struct HudBand {
+    over_arena: bool,
+}
+
+/// Lines the bands up with the arena, which takes up the fractions of the
+/// view set in `GameConfig`.
+fn root_style(config: &GameConfig) -> Style {
+    let side = Val::Percent((1. - config.arena_width_fraction) * 50.);
+    Style {
+        width: Val::Percent(100.),
+        height: Val::Percent(100.),
+        flex_direction: FlexDirection::Column,
+        padding: UiRect::horizontal(side),
+        ..Default::default()
+    }
+}
+
+fn band_style(config: &GameConfig, over_arena: bool) -> Style {
+    let height = if over_arena {
+        config.arena_height_fraction * 100.
+    } else {
+        (1. - config.arena_height_fraction) * 50.
+    };
+    Style {
+        height: Val::Percent(height),
+        flex_direction: FlexDirection::Row,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn setup_hud(mut commands: Commands, config: Res<GameConfig>) {
+    let bands = [
+        (
+            false,
+            &[HudRegion::TopLeft, HudRegion::Top, HudRegion::TopRight][..],
+        ),
+        (true, &[HudRegion::Centre][..]),
+        (
+            false,
+            &[
+                HudRegion::BottomLeft,
+                HudRegion::Bottom,
+                HudRegion::BottomRight,
+            ][..],
+        ),
+    ];
+    let mut regions = HashMap::new();
+    commands
+        .spawn((
+            HudRoot,
+            NodeBundle {
+                style: root_style(&config),
+                ..Default::default()
+            },
+        ))
+        .with_children(|root| {
+            for (over_arena, band) in bands {
+                root.spawn((
+                    HudBand { over_arena },
+                    NodeBundle {
+                        style: band_style(&config, over_arena),
+                        ..Default::default()
+                    },
+                ))
+                .with_children(|band_node| {
+                    for &region in band {
+                        let node = band_node.spawn((
+                            region,
+                            NodeBundle {
+                                style: region.style(),
+                                ..Default::default()
+                            },
+                        ));
+                        regions.insert(region, node.id());
+                    }
+                });
+            }
+        });
+    commands.insert_resource(Hud { regions });
+}
+
+/// Keeps the bands lined up with the arena when the fractions in the config
+/// change.
+pub(crate) fn layout_hud_system(
+    config: Res<GameConfig>,
+    mut roots: Query<&mut Style, (With<HudRoot>, Without<HudBand>)>,
+    mut bands: Query<(&mut Style, &HudBand)>,
+) {
+    for mut style in roots.iter_mut() {
+        *style = root_style(&config);
+    }
+    for (mut style, band) in bands.iter_mut() {
+        *style = band_style(&config, band.over_arena);
+    }
+}
+
+/// How many scores `setup_score` puts on the HUD.
+#[derive(Resource)]
+pub(crate) struct PlayerCount(pub usize);
+
+fn score_text(player: impl Component) -> impl Bundle {
+    (
+        player,
+        ScoreText,
+        TextBundle::from_section(
+            "0",
+            TextStyle {
+                font_size: 36.0,
+                color: Color::WHITE,
+                ..Default::default()
+            },
+        ),
+    )
+}
+
+/// Puts each player's score above their side of the arena.
+pub(crate) fn setup_score(mut commands: Commands, hud: Res<Hud>, players: Res<PlayerCount>) {
+    commands
+        .entity(hud.region(HudRegion::TopLeft))
+        .with_children(|region| {
+            region.spawn(score_text(Player1));
+        });
+    if players.0 < 2 {
+        return;
+    }
+    commands
+        .entity(hud.region(HudRegion::TopRight))
+        .with_children(|region| {
+            region.spawn(score_text(Player2));
+        });
+}
+
+pub(crate) fn score_text_update_system(
+    mut queries: ParamSet<(
+        Query<&mut Text, (With<Player1>, With<ScoreText>)>,
+        Query<&mut Text, (With<Player2>, With<ScoreText>)>,
+    )>,
+    score: Res<Score>,
+) {
+    for mut text in queries.p0().iter_mut() {
+        text.sections[0].value = score.player1.to_string();
+    }
+    for mut text in queries.p1().iter_mut() {
+        text.sections[0].value = score.player2.to_string();
+    }
+}
+
+/// The ball's current speed, shown in debug builds for tuning the speed-up.
+#[cfg(debug_assertions)]
+#[derive(Component)]
+pub(crate) struct SpeedText;
+
+#[cfg(debug_assertions)]
+pub(crate) fn setup_speed_text(mut commands: Commands, hud: Res<Hud>) {
+    commands
+        .entity(hud.region(HudRegion::BottomLeft))
+        .with_children(|region| {
+            region.spawn((
+                SpeedText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..Default::default()
+                    },
+                ),
+            ));
+        });
+}
+
+#[cfg(debug_assertions)]
+pub(crate) fn speed_text_update_system(
+    balls: Query<&Velocity, With<Ball>>,
+    mut query: Query<&mut Text, With<SpeedText>>,
+) {
+    let value = balls
+        .iter()
+        .next()
+        .map(|velocity| format!("Speed: {:.0}", velocity.speed))
+        .unwrap_or_default();
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+
+use crate::GameConfig;
+
+/// Where paddle inputs come from on each fixed tick. Systems that move
+/// paddles from `PaddleInputs` run after `InputSet::Replay`.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputSet {
+    /// Reads the players' controls.
+    Read,
+    /// Records what was read, or replaces it while playing a replay back.
+    Replay,
+}
+
+/// What one player is doing with their paddle on a tick.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PaddleInput {
+    /// How hard the paddle is pushed, from `-FULL` (full speed down) to
+    /// `FULL` (full speed up). Keys and buttons push it all the way and an
+    /// analog stick anywhere in between. It's kept in whole steps so replays
+    /// and the network carry exactly what was simulated.
+    pub movement: i8,
+    /// Launches the ball when it's this player's serve.
+    pub serve: bool,
+    /// Where the paddle is steered to along the way it moves, in whole
+    /// units, while it follows the mouse or a touch. `movement` is ignored
+    /// while there is one.
+    pub target: Option<i16>,
+}
+
+impl PaddleInput {
+    /// `movement` for moving at full speed.
+    pub const FULL: i8 = i8::MAX;
+    const SERVE: u8 = 1;
+    const TARGET: u8 = 2;
+
+    /// Moving up at full speed.
+    pub const UP: PaddleInput = PaddleInput {
+        movement: Self::FULL,
+        serve: false,
+        target: None,
+    };
+    /// Moving down at full speed.
+    pub const DOWN: PaddleInput = PaddleInput {
+        movement: -Self::FULL,
+        serve: false,
+        target: None,
+    };
+
+    /// Input moving at `direction`, from -1 to 1, rounded to the nearest
+    /// step.
+    pub fn moving(direction: f32) -> Self {
+        PaddleInput {
+            movement: (direction.clamp(-1., 1.) * Self::FULL as f32).round() as i8,
+            serve: false,
+            target: None,
+        }
+    }
+
+    /// Input steering towards `position`, rounded to the nearest unit.
+    pub fn towards(position: f32) -> Self {
+        PaddleInput {
+            target: Some(position.round() as i16),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; 4] {
+        let mut flags = 0;
+        if self.serve {
+            flags |= Self::SERVE;
+        }
+        if self.target.is_some() {
+            flags |= Self::TARGET;
+        }
+        let [low, high] = self.target.unwrap_or(0).to_le_bytes();
+        [self.movement as u8, flags, low, high]
+    }
+
+    pub fn from_bytes([movement, flags, low, high]: [u8; 4]) -> Self {
+        PaddleInput {
+            movement: (movement as i8).max(-Self::FULL),
+            serve: flags & Self::SERVE != 0,
+            target: (flags & Self::TARGET != 0).then_some(i16::from_le_bytes([low, high])),
+        }
+    }
+
+    /// The direction to move in, from -1 to 1.
+    pub fn direction(self) -> f32 {
+        self.movement as f32 / Self::FULL as f32
+    }
+
+    /// Where a paddle at `position` along the way it moves gets to after
+    /// `seconds`, before it's kept inside the arena. It heads for the target
+    /// if there is one, no faster than `max_pointer_speed`.
+    pub fn next_position(self, position: f32, config: &GameConfig, seconds: f32) -> f32 {
+        match self.target {
+            Some(target) => {
+                let max_step = config.max_pointer_speed * seconds;
+                position + (target as f32 - position).clamp(-max_step, max_step)
+            }
+            None => position + self.direction() * config.paddle_speed * seconds,
+        }
+    }
+}
+
+/// Inputs for the human paddles on the tick being simulated. Whatever reads
+/// the players' controls fills this in before the paddles move, so the
+/// simulation never looks at the keyboard itself and can be fed from a
+/// replay or the network instead. Single-player games only use `player1`.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PaddleInputs {
+    pub player1: PaddleInput,
+    pub player2: PaddleInput,
+}
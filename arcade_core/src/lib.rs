@@ -0,0 +1,242 @@
+//! Pieces shared by the arcade games: the arena and its walls, the ball and
+//! paddle components, swept AABB collision, the score HUD, the game-state menus,
+//! rebindable controls, gameplay events and replays.
+
+use std::path::PathBuf;
+
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    window::{WindowCreated, WindowResized},
+};
+
+pub mod arena;
+pub mod cli;
+pub mod collision;
+pub mod config;
+pub mod controls;
+pub mod events;
+pub mod fps;
+pub mod headless;
+pub mod hud;
+pub mod input;
+pub mod physics;
+pub mod replay;
+pub mod rng;
+pub mod state;
+pub mod testing;
+
+pub use arena::{view_size, Arena, ArenaLayout, ArenaSetup, Wall};
+pub use collision::{deflect_off_paddle, sweep, Collider, Hit, Side};
+pub use config::{ConfigError, ConfigLock, GameConfig, GameConfigLoader};
+pub use controls::{Action, Binding, InputMap, Player, PlayerBindings, PlayerControls, Pointers};
+pub use events::{
+    BallHitPaddle, BallHitWall, BrickDestroyed, GameEvents, GoalScored, MatchEnded, Resimulating,
+    ServeStarted,
+};
+pub use headless::HeadlessPlugin;
+pub use hud::{Hud, HudRegion, Player1, Player2, Score, ScoreText};
+pub use input::{InputSet, PaddleInput, PaddleInputs};
+pub use physics::{
+    BallCollision, Interpolated, PaddleMotion, PhysicsPlugin, PhysicsSet, Tick, TickLimit,
+};
+pub use replay::{Replay, ReplayError, ReplayMode, REPLAY_VERSION};
+pub use rng::{AgreedSeed, GameRng};
+pub use state::{spawn_menu, GameState, GameStatePlugin, MenuAction};
+
+#[derive(Component)]
+pub struct MainCamera;
+
+#[derive(Component)]
+pub struct Ball;
+
+#[derive(Component)]
+pub struct Paddle;
+
+/// How fast and which way a ball is moving, kept apart so the ball can be
+/// sped up without changing its course, or turned without changing its
+/// speed.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Velocity {
+    /// Units per second.
+    pub speed: f32,
+    /// Unit vector, or zero for a ball standing still.
+    pub direction: Vec2,
+}
+
+impl Velocity {
+    pub fn new(speed: f32, direction: Vec2) -> Self {
+        Velocity {
+            speed,
+            direction: direction.normalize_or_zero(),
+        }
+    }
+
+    pub fn to_vec2(self) -> Vec2 {
+        self.direction * self.speed
+    }
+}
+
+impl From<Vec2> for Velocity {
+    fn from(v: Vec2) -> Self {
+        Velocity::new(v.length(), v)
+    }
+}
+
+/// Sets up the camera, the arena and its walls, the score HUD and the game
+/// states, and moves the ball. Each game adds its own rules on top.
+pub struct ArcadePlugin {
+    /// Shown on the main menu.
+    pub title: &'static str,
+    pub arena: ArenaLayout,
+    /// Tuning used when the config file can't be read.
+    pub config: GameConfig,
+    /// Asset path of the config file. Defaults to `<title>.config.ron`.
+    pub config_path: Option<String>,
+    /// Seed for every match, instead of a random one each time.
+    pub seed: Option<u64>,
+    /// Number of scores shown on the HUD, either 1 or 2.
+    pub players: usize,
+    /// Simulation ticks per second.
+    pub tick_rate: f64,
+    pub replay: ReplayMode,
+    /// Bindings the players start with, and go back to when they're reset.
+    pub controls: InputMap,
+    /// File the players' bindings are loaded from and saved to. They're
+    /// neither loaded nor saved without one.
+    pub controls_path: Option<PathBuf>,
+    /// Game-specific settings, like who controls each paddle, saved with
+    /// replays so they play back the same way.
+    pub setup: String,
+}
+
+pub fn spawn_ball<'a>(
+    commands: &'a mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    radius: f32,
+    velocity: Vec2,
+) -> EntityCommands<'a> {
+    commands.spawn((
+        Ball,
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Rectangle::new(radius, radius))),
+            material: materials.add(Color::WHITE),
+            transform: Transform::from_xyz(0., 0., 0.),
+            ..Default::default()
+        },
+        Velocity::from(velocity),
+        Interpolated::at(Vec3::ZERO),
+    ))
+}
+
+pub fn spawn_paddle<'a>(
+    commands: &'a mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    position: Vec2,
+    size: Vec2,
+) -> EntityCommands<'a> {
+    commands.spawn((
+        Paddle,
+        Collider { size },
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Rectangle::new(size.x, size.y))),
+            material: materials.add(Color::WHITE),
+            transform: Transform::from_translation(position.extend(0.)),
+            ..Default::default()
+        },
+        Interpolated::at(position.extend(0.)),
+        PaddleMotion::at(position),
+    ))
+}
+
+fn setup_camera(mut commands: Commands) {
+    commands.spawn((Camera2dBundle::default(), MainCamera));
+}
+
+impl Plugin for ArcadePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Arena {
+            width: 0.,
+            height: 0.,
+            wall_thickness: 4.,
+        });
+        app.insert_resource(self.arena);
+        app.insert_resource(self.config);
+        app.insert_resource(Score::default());
+        app.insert_resource(hud::PlayerCount(self.players));
+        app.insert_resource(GameRng::new(self.seed.unwrap_or_else(rand::random)));
+        if let Some(seed) = self.seed {
+            app.insert_resource(rng::FixedSeed(seed));
+        }
+        app.init_resource::<PaddleInputs>();
+        app.add_event::<GoalScored>();
+        app.add_event::<BrickDestroyed>();
+        app.add_event::<ServeStarted>();
+        app.add_event::<MatchEnded>();
+        app.add_plugins((
+            config::ConfigPlugin {
+                path: self
+                    .config_path
+                    .clone()
+                    .unwrap_or_else(|| format!("{}.config.ron", self.title.to_lowercase())),
+            },
+            GameStatePlugin { title: self.title },
+            controls::ControlsPlugin {
+                defaults: self.controls.clone(),
+                path: self.controls_path.clone(),
+            },
+            PhysicsPlugin {
+                tick_rate: self.tick_rate,
+            },
+            replay::ReplayPlugin {
+                game: self.title.to_lowercase(),
+                setup: self.setup.clone(),
+                mode: self.replay.clone(),
+            },
+        ));
+        app.configure_sets(
+            FixedUpdate,
+            (InputSet::Read, InputSet::Replay)
+                .chain()
+                .before(PhysicsSet)
+                .run_if(in_state(GameState::Playing)),
+        );
+        for state in [GameState::MainMenu, GameState::GameOver] {
+            app.add_systems(OnExit(state), rng::reseed_for_match);
+        }
+        app.add_systems(
+            Startup,
+            (
+                setup_camera,
+                (
+                    arena::startup,
+                    arena::setup_arena,
+                    hud::setup_hud,
+                    hud::setup_score,
+                )
+                    .chain()
+                    .in_set(ArenaSetup),
+            ),
+        );
+        // added by the UI and window plugins too, but not when running headless
+        app.init_resource::<UiScale>();
+        app.add_event::<WindowCreated>();
+        app.add_event::<WindowResized>();
+        app.add_systems(
+            Update,
+            (
+                hud::score_text_update_system,
+                hud::layout_hud_system.run_if(resource_changed::<GameConfig>),
+                arena::fit_view_system,
+            ),
+        );
+        #[cfg(debug_assertions)]
+        {
+            app.add_systems(Startup, hud::setup_speed_text.after(ArenaSetup));
+            app.add_systems(Update, hud::speed_text_update_system);
+        }
+    }
+}
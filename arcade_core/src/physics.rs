@@ -0,0 +1,298 @@
+use bevy::{app::AppExit, prelude::*, transform::TransformSystem};
+
+use crate::{
+    collision::{deflect_off_paddle, sweep, Collider},
+    events::{BallHitPaddle, BallHitWall, GameEvents},
+    Ball, GameConfig, GameState, Paddle, Velocity, Wall,
+};
+
+/// Moves the balls on each fixed tick while the game is being played,
+/// bouncing them off anything with a `Collider`. Game rules that score or
+/// reset the ball run in `FixedUpdate` before this set, and ones that react
+/// to `BallCollision`s after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhysicsSet;
+
+/// Number of fixed ticks simulated while playing. Everything that needs to
+/// line up across runs (inputs, replays, tests) is keyed on this.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tick(pub u64);
+
+/// Exits the app once a match has run for this many ticks.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickLimit(pub u64);
+
+/// A ball touched something with a `Collider` during a fixed tick. The ball
+/// has already bounced off it by the time this is read.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BallCollision {
+    pub ball: Entity,
+    pub other: Entity,
+    /// Where the ball's centre was when it touched.
+    pub point: Vec2,
+    /// Unit vector pointing out of the face that was hit.
+    pub normal: Vec2,
+}
+
+/// How far a paddle moved over the last tick, for the spin it puts on the
+/// ball. Kept up to date in `PhysicsSet`, so whatever moves the paddle only
+/// has to change its `Transform`.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct PaddleMotion {
+    last: Vec2,
+    /// Units per second.
+    pub velocity: Vec2,
+}
+
+impl PaddleMotion {
+    /// A paddle standing still at `position`.
+    pub fn at(position: Vec2) -> Self {
+        PaddleMotion {
+            last: position,
+            velocity: Vec2::ZERO,
+        }
+    }
+}
+
+/// Most bounces worked out for one ball in a single tick, so a ball wedged
+/// between colliders can't stall the simulation.
+const MAX_BOUNCES: usize = 4;
+
+/// Smooths out an entity simulated in `FixedUpdate` by drawing it part-way
+/// between its last two simulated positions.
+///
+/// The simulation keeps reading and writing `Transform` as usual. Between
+/// ticks the translation is replaced by the blended one, and put back before
+/// the next tick runs. Moving the entity from outside the fixed schedule, such
+/// as resetting the ball from a menu, is picked up as a teleport and snaps
+/// instead of sliding.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Interpolated {
+    previous: Vec3,
+    current: Vec3,
+    rendered: Vec3,
+}
+
+impl Interpolated {
+    pub fn at(translation: Vec3) -> Self {
+        Interpolated {
+            previous: translation,
+            current: translation,
+            rendered: translation,
+        }
+    }
+
+    /// The translation as of the last simulated tick.
+    pub fn simulated(&self) -> Vec3 {
+        self.current
+    }
+
+    fn snap_if_moved(&mut self, translation: Vec3) {
+        if translation != self.rendered {
+            *self = Interpolated::at(translation);
+        }
+    }
+}
+
+/// The ball's speed after being hit with a paddle. A ball already going
+/// faster than the cap is left as it is.
+fn speed_up(speed: f32, config: &GameConfig) -> f32 {
+    if speed < config.max_ball_speed {
+        (speed + config.speed_up).min(config.max_ball_speed)
+    } else {
+        speed
+    }
+}
+
+fn track_paddle_motion(time: Res<Time>, mut paddles: Query<(&Transform, &mut PaddleMotion)>) {
+    for (transform, mut motion) in paddles.iter_mut() {
+        let position = transform.translation.truncate();
+        motion.velocity = (position - motion.last) / time.delta_seconds();
+        motion.last = position;
+    }
+}
+
+/// Sweeps each ball along its velocity for the tick. On touching a collider
+/// the ball stops there, bounces, and carries on with the rest of the tick,
+/// so a fast ball can't pass through a thin wall or paddle between ticks.
+/// Each hit with a paddle speeds the ball up.
+fn move_balls_system(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mut balls: Query<(Entity, &mut Transform, &mut Velocity), With<Ball>>,
+    colliders: Query<
+        (
+            Entity,
+            &Transform,
+            &Collider,
+            Has<Paddle>,
+            Option<&PaddleMotion>,
+            Option<&Wall>,
+        ),
+        Without<Ball>,
+    >,
+    mut collisions: EventWriter<BallCollision>,
+    mut paddle_hits: GameEvents<BallHitPaddle>,
+    mut wall_hits: GameEvents<BallHitWall>,
+) {
+    let half_size = Vec2::splat(config.ball_radius);
+    for (ball, mut transform, mut velocity) in balls.iter_mut() {
+        let mut position = transform.translation.truncate();
+        let mut remaining = time.delta_seconds();
+        for _ in 0..MAX_BOUNCES {
+            let motion = velocity.to_vec2() * remaining;
+            let first = colliders
+                .iter()
+                .filter_map(
+                    |(entity, target, collider, is_paddle, paddle_motion, wall)| {
+                        let centre = target.translation.truncate();
+                        sweep(position, half_size, motion, centre, collider.size).map(|hit| {
+                            let paddle = is_paddle.then_some(paddle_motion);
+                            (hit, entity, centre, collider.size, paddle, wall)
+                        })
+                    },
+                )
+                .min_by(|a, b| a.0.time.total_cmp(&b.0.time));
+            let Some((hit, other, centre, size, paddle, wall)) = first else {
+                position += motion;
+                break;
+            };
+            position += motion * hit.time;
+            remaining *= 1. - hit.time;
+
+            // paddles angle the ball off their hitting face, the long one
+            let hitting_face = if size.x < size.y {
+                hit.normal.x != 0.
+            } else {
+                hit.normal.y != 0.
+            };
+            if let (Some(paddle_motion), true) = (paddle, hitting_face) {
+                let reach = size.max_element() / 2. + config.ball_radius;
+                deflect_off_paddle(
+                    &mut velocity,
+                    (position - centre).dot(hit.normal.perp()) / reach,
+                    hit.normal,
+                    paddle_motion.map_or(Vec2::ZERO, |motion| motion.velocity),
+                    config.max_bounce_angle.to_radians(),
+                    config.spin,
+                );
+                velocity.speed = speed_up(velocity.speed, &config);
+            } else {
+                let d = velocity.direction;
+                velocity.direction = d - 2. * d.dot(hit.normal) * hit.normal;
+            }
+            if paddle.is_some() {
+                paddle_hits.send(BallHitPaddle {
+                    ball,
+                    paddle: other,
+                    point: position,
+                    normal: hit.normal,
+                    speed: velocity.speed,
+                });
+            } else if let Some(wall) = wall {
+                wall_hits.send(BallHitWall {
+                    ball,
+                    wall: other,
+                    side: wall.side,
+                    point: position,
+                    speed: velocity.speed,
+                });
+            }
+            collisions.send(BallCollision {
+                ball,
+                other,
+                point: position,
+                normal: hit.normal,
+            });
+        }
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+fn advance_tick(mut tick: ResMut<Tick>) {
+    tick.0 += 1;
+}
+
+fn exit_at_tick_limit(tick: Res<Tick>, limit: Res<TickLimit>, mut exit: EventWriter<AppExit>) {
+    if tick.0 == limit.0 {
+        info!("stopping after {} ticks", tick.0);
+        exit.send(AppExit);
+    }
+}
+
+fn reset_tick(mut tick: ResMut<Tick>) {
+    tick.0 = 0;
+}
+
+/// Puts back the simulated translation before a fixed tick.
+fn restore_simulated_transforms(mut query: Query<(&mut Transform, &mut Interpolated)>) {
+    for (mut transform, mut interpolated) in query.iter_mut() {
+        interpolated.snap_if_moved(transform.translation);
+        interpolated.previous = interpolated.current;
+        transform.translation = interpolated.current;
+    }
+}
+
+/// Records the simulated translation after a fixed tick.
+fn record_simulated_transforms(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.current = transform.translation;
+        interpolated.rendered = transform.translation;
+    }
+}
+
+fn interpolate_transforms(
+    time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &mut Interpolated)>,
+) {
+    let alpha = time.overstep_fraction();
+    for (mut transform, mut interpolated) in query.iter_mut() {
+        interpolated.snap_if_moved(transform.translation);
+        transform.translation = interpolated.previous.lerp(interpolated.current, alpha);
+        interpolated.rendered = transform.translation;
+    }
+}
+
+/// Runs the simulation at `tick_rate` ticks per second and interpolates
+/// `Transform` for rendering.
+pub struct PhysicsPlugin {
+    pub tick_rate: f64,
+}
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(self.tick_rate));
+        app.init_resource::<Tick>();
+        app.add_event::<BallCollision>();
+        app.add_event::<BallHitPaddle>();
+        app.add_event::<BallHitWall>();
+        app.add_systems(FixedFirst, restore_simulated_transforms);
+        app.add_systems(
+            FixedUpdate,
+            (
+                move_balls_system.after(track_paddle_motion),
+                track_paddle_motion,
+                advance_tick,
+                exit_at_tick_limit
+                    .after(advance_tick)
+                    .run_if(resource_exists::<TickLimit>),
+            )
+                .in_set(PhysicsSet)
+                .run_if(in_state(GameState::Playing)),
+        );
+        app.add_systems(FixedLast, record_simulated_transforms);
+        for from in [GameState::MainMenu, GameState::GameOver] {
+            app.add_systems(
+                OnTransition {
+                    from,
+                    to: GameState::Countdown,
+                },
+                reset_tick,
+            );
+        }
+        app.add_systems(
+            PostUpdate,
+            interpolate_transforms.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
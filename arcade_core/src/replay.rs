@@ -0,0 +1,382 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use thiserror::Error;
+
+use crate::input::{InputSet, PaddleInput, PaddleInputs};
+use crate::rng::{self, GameRng};
+use crate::{Arena, ArenaLayout, ConfigError, ConfigLock, GameConfig, GameState, Tick};
+
+/// Bumped whenever the file layout changes.
+pub const REPLAY_VERSION: u16 = 7;
+const MAGIC: &[u8; 4] = b"ARCR";
+
+/// Everything needed to play a match again: how it was set up and the
+/// paddle inputs on every tick.
+///
+/// On disk the header is followed by the inputs as runs of identical ticks,
+/// which keeps a match down to a few kilobytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    /// The game it was recorded in, e.g. `pong`.
+    pub game: String,
+    /// Game-specific settings, such as who controls each paddle.
+    pub setup: String,
+    pub seed: u64,
+    /// Size of the arena inside the walls.
+    pub arena: Vec2,
+    pub tick_rate: f64,
+    /// Tuning the match was played with.
+    pub config: GameConfig,
+    /// Inputs for each tick from the start of the match.
+    pub inputs: Vec<PaddleInputs>,
+}
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    #[error("could not read replay: {0}")]
+    Io(#[from] io::Error),
+    #[error("not a replay file")]
+    NotAReplay,
+    #[error("replay format version {0} is not supported, expected {REPLAY_VERSION}")]
+    UnsupportedVersion(u16),
+    #[error("replay file is cut short")]
+    Truncated,
+    #[error("replay was recorded in {found}, not {expected}")]
+    WrongGame { expected: String, found: String },
+    #[error("could not write replay: {0}")]
+    Write(io::Error),
+    #[error("{0} is too long to save in a replay")]
+    TooLong(&'static str),
+    #[error("replay tick rate is {0}, expected a number above 0")]
+    InvalidTickRate(f64),
+    #[error("replay arena is {0}, expected a size above 0 both ways")]
+    InvalidArena(Vec2),
+    #[error("replay config is invalid: {0}")]
+    InvalidConfig(#[from] ConfigError),
+}
+
+impl Replay {
+    /// Reads a replay recorded in `game`.
+    pub fn load(path: &Path, game: &str) -> Result<Self, ReplayError> {
+        let replay = Replay::decode(&fs::read(path)?)?;
+        if replay.game != game {
+            return Err(ReplayError::WrongGame {
+                expected: game.to_string(),
+                found: replay.game,
+            });
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        let bytes = self.encode()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(ReplayError::Write)?;
+        }
+        fs::write(path, bytes).map_err(ReplayError::Write)
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, ReplayError> {
+        let game_len =
+            u8::try_from(self.game.len()).map_err(|_| ReplayError::TooLong("game name"))?;
+        let setup_len =
+            u16::try_from(self.setup.len()).map_err(|_| ReplayError::TooLong("setup"))?;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        bytes.push(game_len);
+        bytes.extend_from_slice(self.game.as_bytes());
+        bytes.extend_from_slice(&setup_len.to_le_bytes());
+        bytes.extend_from_slice(self.setup.as_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.arena.x.to_le_bytes());
+        bytes.extend_from_slice(&self.arena.y.to_le_bytes());
+        bytes.extend_from_slice(&self.tick_rate.to_le_bytes());
+        for value in config_values(&self.config) {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let mut ticks = self.inputs.iter().peekable();
+        while let Some(inputs) = ticks.next() {
+            let mut run: u16 = 1;
+            while run < u16::MAX && ticks.next_if_eq(&inputs).is_some() {
+                run += 1;
+            }
+            bytes.extend_from_slice(&run.to_le_bytes());
+            bytes.extend_from_slice(&inputs.player1.to_bytes());
+            bytes.extend_from_slice(&inputs.player2.to_bytes());
+        }
+        Ok(bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+            return Err(ReplayError::NotAReplay);
+        }
+        let version = u16::from_le_bytes(reader.array()?);
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let len = reader.take(1)?[0] as usize;
+        let game = reader.text(len)?;
+        let len = u16::from_le_bytes(reader.array()?) as usize;
+        let setup = reader.text(len)?;
+        let seed = u64::from_le_bytes(reader.array()?);
+        let arena = Vec2::new(
+            f32::from_le_bytes(reader.array()?),
+            f32::from_le_bytes(reader.array()?),
+        );
+        let tick_rate = f64::from_le_bytes(reader.array()?);
+        let mut config = GameConfig::default();
+        for value in config_values_mut(&mut config) {
+            *value = f32::from_le_bytes(reader.array()?);
+        }
+        // played back as they are, so check them like any other settings
+        if !(tick_rate.is_finite() && tick_rate > 0.) {
+            return Err(ReplayError::InvalidTickRate(tick_rate));
+        }
+        if !(arena.is_finite() && arena.cmpgt(Vec2::ZERO).all()) {
+            return Err(ReplayError::InvalidArena(arena));
+        }
+        config.validate()?;
+
+        let mut inputs = Vec::new();
+        while !reader.0.is_empty() {
+            let run = u16::from_le_bytes(reader.array()?);
+            let tick = PaddleInputs {
+                player1: PaddleInput::from_bytes(reader.array()?),
+                player2: PaddleInput::from_bytes(reader.array()?),
+            };
+            inputs.extend(std::iter::repeat_n(tick, run as usize));
+        }
+        Ok(Replay {
+            game,
+            setup,
+            seed,
+            arena,
+            tick_rate,
+            config,
+            inputs,
+        })
+    }
+}
+
+/// The config's values in the order they're saved in.
+fn config_values(config: &GameConfig) -> [f32; 13] {
+    [
+        config.paddle_width,
+        config.paddle_height,
+        config.paddle_speed,
+        config.max_pointer_speed,
+        config.ball_radius,
+        config.ball_speed,
+        config.speed_up,
+        config.max_ball_speed,
+        config.max_bounce_angle,
+        config.spin,
+        config.max_serve_angle,
+        config.arena_width_fraction,
+        config.arena_height_fraction,
+    ]
+}
+
+fn config_values_mut(config: &mut GameConfig) -> [&mut f32; 13] {
+    [
+        &mut config.paddle_width,
+        &mut config.paddle_height,
+        &mut config.paddle_speed,
+        &mut config.max_pointer_speed,
+        &mut config.ball_radius,
+        &mut config.ball_speed,
+        &mut config.speed_up,
+        &mut config.max_ball_speed,
+        &mut config.max_bounce_angle,
+        &mut config.spin,
+        &mut config.max_serve_angle,
+        &mut config.arena_width_fraction,
+        &mut config.arena_height_fraction,
+    ]
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ReplayError> {
+        if self.0.len() < len {
+            return Err(ReplayError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ReplayError> {
+        self.take(N)?.try_into().map_err(|_| ReplayError::Truncated)
+    }
+
+    fn text(&mut self, len: usize) -> Result<String, ReplayError> {
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| ReplayError::NotAReplay)
+    }
+}
+
+/// Whether matches are recorded or a recorded one is played back.
+#[derive(Debug, Clone, Default)]
+pub enum ReplayMode {
+    #[default]
+    Off,
+    /// Saves every match to a new file in this directory.
+    Record(PathBuf),
+    /// Skips the main menu and plays this match instead of reading the
+    /// players' controls.
+    Playback(Replay),
+}
+
+#[derive(Resource)]
+struct ReplayRecorder {
+    dir: PathBuf,
+    replay: Replay,
+    /// Whether a match is being recorded that hasn't been saved yet.
+    recording: bool,
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    finished: bool,
+}
+
+impl ReplayPlayback {
+    /// Whether every recorded input has been played back.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+}
+
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    rng: Res<GameRng>,
+    arena: Res<Arena>,
+    config: Res<GameConfig>,
+    time: Res<Time<Fixed>>,
+) {
+    recorder.replay.seed = rng.seed();
+    recorder.replay.config = *config;
+    recorder.replay.arena = Vec2::new(arena.width, arena.height);
+    recorder.replay.tick_rate = 1. / time.timestep().as_secs_f64();
+    recorder.replay.inputs.clear();
+    recorder.recording = true;
+}
+
+/// Keys the inputs by tick, so a tick that's simulated again overwrites
+/// what was recorded for it.
+fn record_inputs_system(
+    tick: Res<Tick>,
+    inputs: Res<PaddleInputs>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    let inputs_so_far = &mut recorder.replay.inputs;
+    inputs_so_far.truncate(tick.0 as usize);
+    inputs_so_far.push(*inputs);
+}
+
+fn save_recording(mut recorder: ResMut<ReplayRecorder>) {
+    if !recorder.recording {
+        return;
+    }
+    recorder.recording = false;
+    if recorder.replay.inputs.is_empty() {
+        return;
+    }
+    let saved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let path = recorder
+        .dir
+        .join(format!("{}-{saved_at}.replay", recorder.replay.game));
+    match recorder.replay.save(&path) {
+        Ok(()) => info!("saved replay to {}", path.display()),
+        Err(err) => error!("could not save replay to {}: {err}", path.display()),
+    }
+}
+
+fn save_recording_on_exit(exit: EventReader<AppExit>, recorder: ResMut<ReplayRecorder>) {
+    if !exit.is_empty() {
+        save_recording(recorder);
+    }
+}
+
+fn start_playback(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Countdown);
+}
+
+fn playback_inputs_system(
+    tick: Res<Tick>,
+    mut inputs: ResMut<PaddleInputs>,
+    mut playback: ResMut<ReplayPlayback>,
+) {
+    match playback.replay.inputs.get(tick.0 as usize) {
+        Some(recorded) => *inputs = *recorded,
+        None => {
+            *inputs = PaddleInputs::default();
+            if !playback.finished {
+                info!("replay finished after {} ticks", tick.0);
+                playback.finished = true;
+            }
+        }
+    }
+}
+
+/// Records or plays back replays. Added by `ArcadePlugin`.
+pub(crate) struct ReplayPlugin {
+    pub game: String,
+    pub setup: String,
+    pub mode: ReplayMode,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        match &self.mode {
+            ReplayMode::Off => {}
+            ReplayMode::Record(dir) => {
+                app.insert_resource(ReplayRecorder {
+                    dir: dir.clone(),
+                    replay: Replay {
+                        game: self.game.clone(),
+                        setup: self.setup.clone(),
+                        seed: 0,
+                        arena: Vec2::ZERO,
+                        tick_rate: 0.,
+                        config: GameConfig::default(),
+                        inputs: Vec::new(),
+                    },
+                    recording: false,
+                });
+                app.insert_resource(ConfigLock::Match);
+                for state in [GameState::MainMenu, GameState::GameOver] {
+                    app.add_systems(OnExit(state), start_recording.after(rng::reseed_for_match));
+                    app.add_systems(OnEnter(state), save_recording);
+                }
+                app.add_systems(FixedUpdate, record_inputs_system.in_set(InputSet::Replay));
+                app.add_systems(Last, save_recording_on_exit);
+            }
+            ReplayMode::Playback(replay) => {
+                app.world.resource_mut::<ArenaLayout>().size = replay.arena;
+                app.insert_resource(Time::<Fixed>::from_hz(replay.tick_rate));
+                app.insert_resource(replay.config);
+                app.insert_resource(ConfigLock::Session);
+                app.insert_resource(ReplayPlayback {
+                    replay: replay.clone(),
+                    finished: false,
+                });
+                app.add_systems(Startup, start_playback);
+                app.add_systems(FixedUpdate, playback_inputs_system.in_set(InputSet::Replay));
+            }
+        }
+    }
+}
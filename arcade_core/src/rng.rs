@@ -0,0 +1,74 @@
+use std::ops::{Deref, DerefMut};
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::replay::ReplayPlayback;
+
+/// The random numbers used by the simulation. Every random decision in a
+/// game, from which way the ball is served to how far off the computer aims,
+/// should be drawn from here rather than `rand::random`.
+///
+/// It's reseeded at the start of every match, so a match can be played again
+/// from its seed and inputs.
+#[derive(Resource, Clone)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed the current match started from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Deref for GameRng {
+    type Target = StdRng;
+
+    fn deref(&self) -> &StdRng {
+        &self.rng
+    }
+}
+
+impl DerefMut for GameRng {
+    fn deref_mut(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+}
+
+/// Seed every match starts from in place of a random one.
+#[derive(Resource)]
+pub(crate) struct FixedSeed(pub u64);
+
+/// Seed for the next match, agreed with someone who has to play it out the
+/// same way, such as an online peer.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AgreedSeed(pub u64);
+
+/// Picks the seed for a new match: the replay's when one is playing back,
+/// then an agreed or fixed seed, and otherwise a random one. Runs on leaving
+/// the menus, before any game sets the match up.
+pub(crate) fn reseed_for_match(
+    mut rng: ResMut<GameRng>,
+    fixed: Option<Res<FixedSeed>>,
+    agreed: Option<Res<AgreedSeed>>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    let seed = match (playback, agreed, fixed) {
+        (Some(playback), _, _) => playback.replay.seed,
+        (None, Some(agreed), _) => agreed.0,
+        (None, None, Some(fixed)) => fixed.0,
+        (None, None, None) => rand::random(),
+    };
+    info!("match seed {seed}");
+    *rng = GameRng::new(seed);
+}
@@ -0,0 +1,354 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::controls::{self, Action, Player, PlayerControls, Rebinding};
+use crate::hud::PlayerCount;
+
+const COUNTDOWN_SECONDS: f32 = 3.;
+const SELECTED_COLOR: Color = Color::YELLOW;
+const UNSELECTED_COLOR: Color = Color::WHITE;
+
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    /// Counting down before play starts or resumes.
+    Countdown,
+    Playing,
+    Paused,
+    GameOver,
+    /// Changing the key bindings, from the main menu.
+    Controls,
+}
+
+/// What picking a menu entry does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    Play,
+    Resume,
+    MainMenu,
+    /// Opens the controls menu.
+    Controls,
+    /// Waits for a new key, button or stick for this player's action.
+    Rebind(Player, Action),
+    /// Switches whether the player's paddle follows the mouse.
+    TogglePointer(Player),
+    /// Puts back every binding the game started with.
+    ResetControls,
+    Quit,
+}
+
+/// Sent when a menu entry is picked that doesn't just change state.
+#[derive(Event, Debug, Clone, Copy)]
+pub(crate) struct MenuPicked(pub MenuAction);
+
+/// Adds the game states, the main and pause menus, the countdown and
+/// keyboard/gamepad menu navigation. Games spawn their own game-over screen
+/// with `spawn_menu` on `OnEnter(GameState::GameOver)`. Pausing reads the
+/// players' controls, so it needs the `InputMap` that `ArcadePlugin` adds.
+pub struct GameStatePlugin {
+    pub title: &'static str,
+}
+
+#[derive(Resource)]
+struct Title(&'static str);
+
+#[derive(Resource)]
+struct Countdown(Timer);
+
+/// Index of the highlighted entry in the open menu.
+#[derive(Resource, Default)]
+pub(crate) struct MenuSelection(usize);
+
+#[derive(Component)]
+struct MenuRoot;
+
+#[derive(Component)]
+pub(crate) struct MenuItem {
+    index: usize,
+    pub action: MenuAction,
+}
+
+#[derive(Component)]
+struct CountdownText;
+
+/// Spawns a full-screen menu with a title, an optional line of text under
+/// it and a list of entries. The menu is removed when its state is left.
+pub fn spawn_menu(
+    commands: &mut Commands,
+    title: &str,
+    subtitle: Option<String>,
+    items: &[(&str, MenuAction)],
+) {
+    spawn_menu_sized(commands, title, subtitle, items, 40.);
+}
+
+/// `spawn_menu` with the entries at `font_size`, for fitting in a long list.
+pub(crate) fn spawn_menu_sized(
+    commands: &mut Commands,
+    title: &str,
+    subtitle: Option<String>,
+    items: &[(&str, MenuAction)],
+    font_size: f32,
+) {
+    commands.insert_resource(MenuSelection(0));
+    commands
+        .spawn((
+            MenuRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.),
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::BLACK.with_a(0.6)),
+                z_index: ZIndex::Global(i32::MAX - 1),
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font_size: 80.0,
+                    color: Color::WHITE,
+                    ..Default::default()
+                },
+            ));
+            if let Some(subtitle) = subtitle {
+                parent.spawn(TextBundle::from_section(
+                    subtitle,
+                    TextStyle {
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                        ..Default::default()
+                    },
+                ));
+            }
+            for (index, (label, action)) in items.iter().enumerate() {
+                parent.spawn((
+                    MenuItem {
+                        index,
+                        action: *action,
+                    },
+                    TextBundle::from_section(
+                        *label,
+                        TextStyle {
+                            font_size,
+                            color: if index == 0 {
+                                SELECTED_COLOR
+                            } else {
+                                UNSELECTED_COLOR
+                            },
+                            ..Default::default()
+                        },
+                    ),
+                ));
+            }
+        });
+}
+
+fn setup_main_menu(mut commands: Commands, title: Res<Title>) {
+    spawn_menu(
+        &mut commands,
+        title.0,
+        None,
+        &[
+            ("Play", MenuAction::Play),
+            ("Controls", MenuAction::Controls),
+            ("Quit", MenuAction::Quit),
+        ],
+    );
+}
+
+fn setup_pause_menu(mut commands: Commands) {
+    spawn_menu(
+        &mut commands,
+        "Paused",
+        None,
+        &[
+            ("Resume", MenuAction::Resume),
+            ("Main menu", MenuAction::MainMenu),
+            ("Quit", MenuAction::Quit),
+        ],
+    );
+}
+
+fn teardown_menu(mut commands: Commands, query: Query<Entity, With<MenuRoot>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub(crate) fn menu_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<ButtonInput<GamepadButton>>,
+    mut selection: ResMut<MenuSelection>,
+    mut items: Query<(&MenuItem, &mut Text)>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut picked: EventWriter<MenuPicked>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let gamepad_pressed = |button_type| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_input.just_pressed(GamepadButton::new(gamepad, button_type)))
+    };
+    let up = keyboard_input.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW])
+        || gamepad_pressed(GamepadButtonType::DPadUp);
+    let down = keyboard_input.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS])
+        || gamepad_pressed(GamepadButtonType::DPadDown);
+    let confirm = keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::Space])
+        || gamepad_pressed(GamepadButtonType::South);
+
+    let count = items.iter().count();
+    if count == 0 {
+        return;
+    }
+    if up {
+        selection.0 = (selection.0 + count - 1) % count;
+    }
+    if down {
+        selection.0 = (selection.0 + 1) % count;
+    }
+
+    for (item, mut text) in items.iter_mut() {
+        text.sections[0].style.color = if item.index == selection.0 {
+            SELECTED_COLOR
+        } else {
+            UNSELECTED_COLOR
+        };
+        if confirm && item.index == selection.0 {
+            match item.action {
+                MenuAction::Play | MenuAction::Resume => next_state.set(GameState::Countdown),
+                MenuAction::MainMenu => next_state.set(GameState::MainMenu),
+                MenuAction::Controls => next_state.set(GameState::Controls),
+                MenuAction::Rebind(..)
+                | MenuAction::TogglePointer(_)
+                | MenuAction::ResetControls => {
+                    picked.send(MenuPicked(item.action));
+                }
+                MenuAction::Quit => {
+                    exit.send(AppExit);
+                }
+            }
+        }
+    }
+}
+
+/// Any player's pause binding pauses play, and resumes it again from the
+/// pause menu.
+fn pause_input_system(
+    controls: PlayerControls,
+    count: Res<PlayerCount>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let pressed = controls::players(&count)
+        .iter()
+        .any(|&player| controls.just_pressed(player, Action::Pause));
+    if !pressed {
+        return;
+    }
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Countdown),
+        _ => {}
+    }
+}
+
+fn setup_countdown(mut commands: Commands) {
+    commands.insert_resource(Countdown(Timer::from_seconds(
+        COUNTDOWN_SECONDS,
+        TimerMode::Once,
+    )));
+    commands
+        .spawn((
+            CountdownText,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 120.0,
+                    color: Color::WHITE,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+fn countdown_system(
+    time: Res<Time>,
+    mut countdown: ResMut<Countdown>,
+    roots: Query<&Children, With<CountdownText>>,
+    mut texts: Query<&mut Text>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if countdown.0.tick(time.delta()).finished() {
+        next_state.set(GameState::Playing);
+        return;
+    }
+    let remaining = countdown.0.remaining_secs().ceil();
+    for children in roots.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.sections[0].value = remaining.to_string();
+            }
+        }
+    }
+}
+
+fn teardown_countdown(mut commands: Commands, query: Query<Entity, With<CountdownText>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Countdown>();
+}
+
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Title(self.title));
+        app.init_resource::<MenuSelection>();
+        app.add_event::<MenuPicked>();
+        app.init_state::<GameState>();
+        app.add_systems(OnEnter(GameState::MainMenu), setup_main_menu);
+        app.add_systems(OnExit(GameState::MainMenu), teardown_menu);
+        app.add_systems(OnEnter(GameState::Paused), setup_pause_menu);
+        app.add_systems(OnExit(GameState::Paused), teardown_menu);
+        app.add_systems(OnExit(GameState::GameOver), teardown_menu);
+        app.add_systems(OnExit(GameState::Controls), teardown_menu);
+        app.add_systems(OnEnter(GameState::Countdown), setup_countdown);
+        app.add_systems(OnExit(GameState::Countdown), teardown_countdown);
+        app.add_systems(
+            Update,
+            (
+                menu_input_system.run_if(
+                    in_state(GameState::MainMenu)
+                        .or_else(in_state(GameState::Paused))
+                        .or_else(in_state(GameState::GameOver))
+                        .or_else(in_state(GameState::Controls))
+                        .and_then(not(resource_exists::<Rebinding>)),
+                ),
+                pause_input_system
+                    .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+                countdown_system.run_if(in_state(GameState::Countdown)),
+            ),
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Runs a game headlessly from tests, a fixed tick at a time.
+
+use std::time::Duration;
+
+use bevy::ecs::query::QueryFilter;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use crate::{Ball, GameState, HeadlessPlugin, InputSet, Interpolated, PaddleInputs, Score, Tick};
+
+/// Inputs that replace the players' controls while set.
+#[derive(Resource, Default)]
+struct ScriptedInputs(Option<PaddleInputs>);
+
+fn scripted_inputs_system(script: Res<ScriptedInputs>, mut inputs: ResMut<PaddleInputs>) {
+    if let Some(scripted) = script.0 {
+        *inputs = scripted;
+    }
+}
+
+/// A game running under `HeadlessPlugin`, with the clock moving forward by
+/// exactly one fixed tick per update instead of following the wall clock.
+pub struct TestApp {
+    app: App,
+}
+
+impl TestApp {
+    /// Builds the app around `game`.
+    pub fn new(game: impl Plugin) -> Self {
+        let mut app = App::new();
+        app.add_plugins((HeadlessPlugin, game));
+        app.init_resource::<ScriptedInputs>();
+        app.add_systems(FixedUpdate, scripted_inputs_system.in_set(InputSet::Replay));
+        let timestep = app.world.resource::<Time<Fixed>>().timestep();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+        app.finish();
+        app.cleanup();
+        TestApp { app }
+    }
+
+    /// Runs startup and the new-match setup, then skips the countdown so the
+    /// next tick is the first of the match.
+    pub fn start_match(&mut self) {
+        self.app.update();
+        self.set_state(GameState::Playing);
+    }
+
+    pub fn set_state(&mut self, state: GameState) {
+        self.app
+            .world
+            .resource_mut::<NextState<GameState>>()
+            .set(state);
+        self.app.update();
+    }
+
+    pub fn state(&self) -> GameState {
+        *self.app.world.resource::<State<GameState>>().get()
+    }
+
+    /// Holds these inputs on every tick until `release_inputs`.
+    pub fn hold_inputs(&mut self, inputs: PaddleInputs) {
+        self.app.world.resource_mut::<ScriptedInputs>().0 = Some(inputs);
+    }
+
+    pub fn release_inputs(&mut self) {
+        self.app.world.resource_mut::<ScriptedInputs>().0 = None;
+    }
+
+    /// Runs updates until `ticks` more fixed ticks have been simulated.
+    pub fn run_ticks(&mut self, ticks: u32) {
+        let time = self.app.world.resource::<Time<Fixed>>();
+        let target = time.elapsed() + time.timestep() * ticks;
+        while self.app.world.resource::<Time<Fixed>>().elapsed() < target {
+            self.app.update();
+        }
+    }
+
+    /// Runs ticks until `condition` holds, up to `max_ticks`. Returns whether
+    /// it ever did.
+    pub fn run_until(&mut self, max_ticks: u32, condition: impl Fn(&mut World) -> bool) -> bool {
+        for _ in 0..max_ticks {
+            if condition(&mut self.app.world) {
+                return true;
+            }
+            self.run_ticks(1);
+        }
+        condition(&mut self.app.world)
+    }
+
+    /// Runs updates, giving assets time to load in the background, until
+    /// `condition` holds or `timeout` passes. Returns whether it ever did.
+    pub fn wait_for(&mut self, timeout: Duration, condition: impl Fn(&mut World) -> bool) -> bool {
+        let step = Duration::from_millis(1);
+        let mut waited = Duration::ZERO;
+        while !condition(&mut self.app.world) {
+            if waited >= timeout {
+                return false;
+            }
+            std::thread::sleep(step);
+            waited += step;
+            self.app.update();
+        }
+        true
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.app.world.resource::<Tick>().0
+    }
+
+    pub fn score(&self) -> Score {
+        *self.app.world.resource::<Score>()
+    }
+
+    /// Where the simulation has the ball, rather than where it was last drawn.
+    pub fn ball_position(&mut self) -> Vec2 {
+        let mut balls = self
+            .app
+            .world
+            .query_filtered::<(&Transform, Option<&Interpolated>), With<Ball>>();
+        let (transform, interpolated) = balls.single(&self.app.world);
+        interpolated
+            .map_or(transform.translation, Interpolated::simulated)
+            .truncate()
+    }
+
+    /// Number of entities matching `F`, e.g. `count::<With<Ball>>()`.
+    pub fn count<F: QueryFilter>(&mut self) -> usize {
+        let mut query = self.app.world.query_filtered::<Entity, F>();
+        query.iter(&self.app.world).count()
+    }
+
+    pub fn world(&self) -> &World {
+        &self.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+}
@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use arcade_core::cli::{Args, CommonArgs};
+use arcade_core::ReplayMode;
+use bevy::math::Vec2;
+
+fn parse(args: &[&str]) -> Result<CommonArgs, String> {
+    let mut args = Args::new(args.iter().copied());
+    let common = CommonArgs::parse(&mut args, "pong")?;
+    args.finish()?;
+    Ok(common)
+}
+
+#[test]
+fn reads_the_shared_options() {
+    let common = parse(&[
+        "--window",
+        "1280x720",
+        "--seed",
+        "42",
+        "--ticks",
+        "600",
+        "--tick-rate",
+        "60",
+    ])
+    .unwrap();
+    assert_eq!(common.window_size, Some(Vec2::new(1280., 720.)));
+    assert_eq!(common.seed, Some(42));
+    assert_eq!(common.ticks, Some(600));
+    assert_eq!(common.tick_rate, Some(60.));
+    assert!(matches!(common.replay_mode(), ReplayMode::Record(dir) if dir == Path::new("replays")));
+
+    let common = parse(&["--no-record"]).unwrap();
+    assert!(matches!(common.replay_mode(), ReplayMode::Off));
+}
+
+#[test]
+fn rejects_options_that_clash() {
+    for args in [
+        &["--headless", "--fullscreen"][..],
+        &["--window", "800x600", "--fullscreen"],
+        &["--record", "out", "--no-record"],
+        &["--ticks", "0"],
+        &["--tick-rate", "0"],
+        &["--tick-rate", "-60"],
+        &["--tick-rate", "inf"],
+        &["--seed", "1", "--seed", "2"],
+    ] {
+        assert!(parse(args).is_err(), "{args:?} should be rejected");
+    }
+}
+
+#[test]
+fn explains_bad_values() {
+    assert_eq!(
+        parse(&["--window", "big"]).unwrap_err(),
+        "invalid value 'big' for --window: expected <width>x<height>, e.g. 1280x720"
+    );
+    assert_eq!(parse(&["--seed"]).unwrap_err(), "--seed needs a value");
+    assert_eq!(
+        parse(&["--tick-rate", "0"]).unwrap_err(),
+        "--tick-rate must be above 0"
+    );
+    assert_eq!(
+        parse(&["--sead", "1"]).unwrap_err(),
+        "unexpected argument '--sead'"
+    );
+}
@@ -0,0 +1,198 @@
+use std::f32::consts::FRAC_PI_4;
+use std::time::Duration;
+
+use arcade_core::{
+    deflect_off_paddle, sweep, Ball, BallCollision, Collider, GameConfig, GameState, PhysicsPlugin,
+    Velocity,
+};
+use bevy::prelude::*;
+
+const BALL: Vec2 = Vec2::splat(5.);
+/// A paddle ten units thick, facing left and right.
+const PADDLE: Vec2 = Vec2::new(10., 100.);
+
+#[test]
+fn a_fast_ball_hits_a_thin_paddle_it_would_jump_over() {
+    // from well to the left to well to the right of it in one step
+    let hit = sweep(
+        Vec2::new(-500., 0.),
+        BALL,
+        Vec2::new(1000., 0.),
+        Vec2::ZERO,
+        PADDLE,
+    )
+    .unwrap();
+    assert_eq!(hit.normal, Vec2::NEG_X);
+    // touching once the ball's edge meets the paddle's face
+    assert!((hit.time - 490. / 1000.).abs() < 1e-6);
+
+    let miss = sweep(
+        Vec2::new(-500., 60.),
+        BALL,
+        Vec2::new(1000., 0.),
+        Vec2::ZERO,
+        PADDLE,
+    );
+    assert_eq!(miss, None);
+}
+
+#[test]
+fn a_ball_already_overlapping_hits_straight_away_through_the_nearest_face() {
+    let hit = sweep(
+        Vec2::new(-8., 20.),
+        BALL,
+        Vec2::new(10., 0.),
+        Vec2::ZERO,
+        PADDLE,
+    )
+    .unwrap();
+    assert_eq!(hit.time, 0.);
+    assert_eq!(hit.normal, Vec2::NEG_X);
+
+    let hit = sweep(
+        Vec2::new(0., 52.),
+        BALL,
+        Vec2::new(0., -10.),
+        Vec2::ZERO,
+        PADDLE,
+    )
+    .unwrap();
+    assert_eq!(hit.time, 0.);
+    assert_eq!(hit.normal, Vec2::Y);
+}
+
+#[test]
+fn a_ball_moving_away_hits_nothing() {
+    // resting against the left face and heading off
+    let start = Vec2::new(-10., 0.);
+    assert_eq!(
+        sweep(start, BALL, Vec2::new(-10., 0.), Vec2::ZERO, PADDLE),
+        None
+    );
+    // overlapping, but on its way out
+    assert_eq!(
+        sweep(
+            Vec2::new(-8., 0.),
+            BALL,
+            Vec2::new(-10., 0.),
+            Vec2::ZERO,
+            PADDLE
+        ),
+        None
+    );
+    // heading away from a box far behind it
+    assert_eq!(
+        sweep(
+            Vec2::new(-500., 0.),
+            BALL,
+            Vec2::new(-1000., 0.),
+            Vec2::ZERO,
+            PADDLE
+        ),
+        None
+    );
+}
+
+#[test]
+fn a_ball_bounces_off_two_walls_in_one_step() {
+    let mut app = App::new();
+    app.add_plugins(PhysicsPlugin { tick_rate: 60. });
+    app.add_event::<bevy::app::AppExit>();
+    app.insert_state(GameState::Playing);
+    let config = GameConfig::default();
+    app.insert_resource(config);
+    let mut time = Time::<()>::default();
+    time.advance_by(Duration::from_secs(1));
+    app.insert_resource(time);
+
+    // a corner up and to the right, with the ball heading into it fast
+    // enough to reach both walls and come back out within the second
+    let walls = [
+        (Vec2::new(50., 0.), Vec2::new(10., 1000.)),
+        (Vec2::new(0., 50.), Vec2::new(1000., 10.)),
+    ];
+    for (position, size) in walls {
+        app.world.spawn((
+            Transform::from_translation(position.extend(0.)),
+            Collider { size },
+        ));
+    }
+    let ball = app
+        .world
+        .spawn((
+            Ball,
+            Transform::default(),
+            Velocity::new(80. * 2f32.sqrt(), Vec2::ONE.normalize()),
+        ))
+        .id();
+    app.world.run_schedule(FixedUpdate);
+
+    let collisions = app.world.resource::<Events<BallCollision>>();
+    let normals: Vec<_> = collisions
+        .get_reader()
+        .read(collisions)
+        .map(|collision| collision.normal)
+        .collect();
+    assert_eq!(normals.len(), 2);
+    assert!(normals.contains(&Vec2::NEG_X) && normals.contains(&Vec2::NEG_Y));
+
+    let velocity = app.world.get::<Velocity>(ball).unwrap();
+    assert!(velocity.direction.abs_diff_eq(-Vec2::ONE.normalize(), 1e-5));
+    // each wall's face is 45 - 7 from the start, and the ball goes 80 along
+    // each axis, so it ends up as far back from the faces as it had left over
+    let reach = 45. - config.ball_radius;
+    let position = app
+        .world
+        .get::<Transform>(ball)
+        .unwrap()
+        .translation
+        .truncate();
+    assert!(position.abs_diff_eq(Vec2::splat(reach - (80. - reach)), 1e-3));
+}
+
+/// A ball coming in flat at a paddle facing right.
+fn incoming() -> Velocity {
+    Velocity::new(300., Vec2::NEG_X)
+}
+
+#[test]
+fn the_contact_point_sets_the_angle_and_not_the_speed() {
+    let mut velocity = incoming();
+    deflect_off_paddle(&mut velocity, 0., Vec2::X, Vec2::ZERO, FRAC_PI_4, 0.5);
+    assert_eq!(velocity, Velocity::new(300., Vec2::X));
+
+    let mut velocity = incoming();
+    deflect_off_paddle(&mut velocity, 1., Vec2::X, Vec2::ZERO, FRAC_PI_4, 0.5);
+    assert_eq!(velocity.speed, 300.);
+    assert!((Vec2::X.angle_between(velocity.direction) - FRAC_PI_4).abs() < 1e-5);
+
+    let mut velocity = incoming();
+    deflect_off_paddle(&mut velocity, -0.5, Vec2::X, Vec2::ZERO, FRAC_PI_4, 0.5);
+    assert!(velocity.direction.y < 0.);
+}
+
+#[test]
+fn a_moving_paddle_cuts_the_ball_up_to_the_largest_angle() {
+    let mut velocity = incoming();
+    deflect_off_paddle(
+        &mut velocity,
+        0.,
+        Vec2::X,
+        Vec2::new(0., 200.),
+        FRAC_PI_4,
+        0.5,
+    );
+    assert_eq!(velocity.speed, 300.);
+    assert!(velocity.direction.y > 0.);
+
+    let mut velocity = incoming();
+    deflect_off_paddle(
+        &mut velocity,
+        1.,
+        Vec2::X,
+        Vec2::new(0., 5000.),
+        FRAC_PI_4,
+        0.5,
+    );
+    assert!((Vec2::X.angle_between(velocity.direction) - FRAC_PI_4).abs() < 1e-5);
+}
@@ -0,0 +1,60 @@
+use arcade_core::{ConfigError, GameConfig};
+
+const VALID: &str = "(
+    paddle_width: 10.0,
+    paddle_height: 50.0,
+    paddle_speed: 200.0,
+    max_pointer_speed: 800.0,
+    ball_radius: 7.0,
+    ball_speed: 200.0,
+    speed_up: 10.0,
+    max_ball_speed: 400.0,
+    max_bounce_angle: 45.0,
+    spin: 0.5,
+    max_serve_angle: 30.0,
+    arena_width_fraction: 0.9,
+    arena_height_fraction: 0.8,
+)";
+
+#[test]
+fn reads_a_valid_config() {
+    let config = GameConfig::from_ron(VALID.as_bytes()).unwrap();
+    assert_eq!(config.paddle_height, 50.);
+    assert_eq!(config.arena_width_fraction, 0.9);
+}
+
+#[test]
+fn names_the_value_that_is_out_of_range() {
+    let config = VALID.replace("ball_speed: 200.0", "ball_speed: -1.0");
+    let err = GameConfig::from_ron(config.as_bytes()).unwrap_err();
+    assert!(matches!(
+        err,
+        ConfigError::Invalid {
+            field: "ball_speed",
+            ..
+        }
+    ));
+
+    let config = VALID.replace("arena_height_fraction: 0.8", "arena_height_fraction: 1.5");
+    let err = GameConfig::from_ron(config.as_bytes()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "arena_height_fraction is 1.5, expected a fraction above 0 and at most 1"
+    );
+
+    let config = VALID.replace("max_ball_speed: 400.0", "max_ball_speed: 100.0");
+    let err = GameConfig::from_ron(config.as_bytes()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "max_ball_speed is 100, expected ball_speed or more"
+    );
+}
+
+#[test]
+fn rejects_misspelt_names() {
+    let config = VALID.replace("max_bounce_angle", "max_bounce_angel");
+    assert!(matches!(
+        GameConfig::from_ron(config.as_bytes()),
+        Err(ConfigError::Ron(_))
+    ));
+}
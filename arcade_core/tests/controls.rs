@@ -0,0 +1,81 @@
+use arcade_core::{Action, Binding, InputMap, PaddleInput, Player, PlayerControls, Pointers};
+use bevy::input::gamepad::{
+    GamepadAxisChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadInfo,
+};
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+
+/// What player 1's controls read on the last update, steering with pointers
+/// on the left half.
+#[derive(Resource, Default)]
+struct Read(PaddleInput);
+
+fn read_system(controls: PlayerControls, mut read: ResMut<Read>) {
+    read.0 = controls.paddle_input(Player::One, |point| (point.x < 0.).then_some(point.y));
+}
+
+fn app_with_gamepad() -> App {
+    let mut app = App::new();
+    app.add_plugins(InputPlugin);
+    app.insert_resource(InputMap::default());
+    app.init_resource::<Pointers>();
+    app.init_resource::<Read>();
+    app.add_systems(Update, read_system);
+    app.world.send_event(GamepadConnectionEvent::new(
+        Gamepad::new(0),
+        GamepadConnection::Connected(GamepadInfo {
+            name: "pad".to_string(),
+        }),
+    ));
+    app
+}
+
+#[test]
+fn a_stick_part_way_over_moves_the_paddle_at_part_speed() {
+    let mut app = app_with_gamepad();
+    app.world.send_event(GamepadAxisChangedEvent::new(
+        Gamepad::new(0),
+        GamepadAxisType::LeftStickY,
+        0.5,
+    ));
+    app.update();
+    assert_eq!(app.world.resource::<Read>().0, PaddleInput::moving(0.5));
+    assert!((app.world.resource::<Read>().0.direction() - 0.5).abs() < 0.01);
+
+    // a key pushes all the way, against the stick
+    app.world
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyS);
+    app.update();
+    assert_eq!(app.world.resource::<Read>().0, PaddleInput::moving(-0.5));
+}
+
+#[test]
+fn a_touch_steers_only_the_paddle_whose_side_it_is_on() {
+    let mut app = app_with_gamepad();
+    app.world.resource_mut::<Pointers>().touches = vec![Vec2::new(100., -20.)];
+    app.update();
+    assert_eq!(app.world.resource::<Read>().0, PaddleInput::default());
+
+    app.world
+        .resource_mut::<Pointers>()
+        .touches
+        .push(Vec2::new(-100., 50.));
+    app.update();
+    assert_eq!(app.world.resource::<Read>().0, PaddleInput::towards(50.));
+}
+
+#[test]
+fn rebinding_replaces_only_the_same_kind_of_control() {
+    let mut map = InputMap::default();
+    map.player1
+        .rebind(Action::MoveUp, Binding::Key(KeyCode::KeyI));
+    let bindings = map.get(Player::One).get(Action::MoveUp);
+    assert!(bindings.contains(&Binding::Key(KeyCode::KeyI)));
+    assert!(!bindings.contains(&Binding::Key(KeyCode::KeyW)));
+    assert!(bindings.contains(&Binding::Button(GamepadButtonType::DPadUp)));
+    assert_eq!(bindings.len(), 3);
+
+    let saved = map.to_ron().unwrap();
+    assert_eq!(InputMap::from_ron(saved.as_bytes()).unwrap(), map);
+}
@@ -0,0 +1,112 @@
+use arcade_core::{GameConfig, PaddleInput, PaddleInputs, Replay, ReplayError};
+use bevy::prelude::*;
+
+fn recorded(inputs: Vec<PaddleInputs>) -> Replay {
+    Replay {
+        game: "pong".to_string(),
+        setup: "human,hard".to_string(),
+        seed: 0x1234_5678_9abc_def0,
+        arena: Vec2::new(800., 400.),
+        tick_rate: 120.,
+        config: GameConfig {
+            ball_speed: 333.,
+            spin: 0.25,
+            ..Default::default()
+        },
+        inputs,
+    }
+}
+
+#[test]
+fn a_replay_decodes_to_what_was_encoded() {
+    let moving = PaddleInputs {
+        player1: PaddleInput::moving(0.5),
+        player2: PaddleInput::towards(-80.),
+    };
+    let serving = PaddleInputs {
+        player1: PaddleInput {
+            serve: true,
+            ..PaddleInput::UP
+        },
+        ..Default::default()
+    };
+    let mut inputs = vec![PaddleInputs::default(); 3];
+    inputs.push(moving);
+    inputs.push(serving);
+    // longer than one run can hold
+    inputs.extend(vec![moving; u16::MAX as usize + 10]);
+    inputs.push(PaddleInputs::default());
+    let replay = recorded(inputs);
+
+    let bytes = replay.encode().unwrap();
+    // the long stretch is kept as two runs rather than a tick at a time
+    assert!(bytes.len() < 200);
+    assert_eq!(Replay::decode(&bytes).unwrap(), replay);
+
+    let empty = recorded(Vec::new());
+    assert_eq!(Replay::decode(&empty.encode().unwrap()).unwrap(), empty);
+}
+
+#[test]
+fn a_replay_cut_short_is_not_read() {
+    let bytes = recorded(vec![PaddleInputs::default(); 10])
+        .encode()
+        .unwrap();
+    assert!(matches!(
+        Replay::decode(&bytes[..bytes.len() - 3]),
+        Err(ReplayError::Truncated)
+    ));
+    assert!(matches!(
+        Replay::decode(b"PNG3"),
+        Err(ReplayError::NotAReplay)
+    ));
+}
+
+#[test]
+fn names_too_long_to_save_are_refused() {
+    let mut replay = recorded(Vec::new());
+    replay.game = "p".repeat(256);
+    assert!(matches!(replay.encode(), Err(ReplayError::TooLong(_))));
+
+    replay.game = "pong".to_string();
+    replay.setup = "s".repeat(u16::MAX as usize + 1);
+    assert!(matches!(replay.encode(), Err(ReplayError::TooLong(_))));
+}
+
+#[test]
+fn a_replay_without_a_usable_tick_rate_is_not_read() {
+    for tick_rate in [0., -60., f64::NAN, f64::INFINITY] {
+        let mut replay = recorded(Vec::new());
+        replay.tick_rate = tick_rate;
+        assert!(matches!(
+            Replay::decode(&replay.encode().unwrap()),
+            Err(ReplayError::InvalidTickRate(_))
+        ));
+    }
+}
+
+#[test]
+fn a_replay_without_a_usable_arena_is_not_read() {
+    for arena in [
+        Vec2::ZERO,
+        Vec2::new(800., -400.),
+        Vec2::new(f32::NAN, 400.),
+    ] {
+        let mut replay = recorded(Vec::new());
+        replay.arena = arena;
+        assert!(matches!(
+            Replay::decode(&replay.encode().unwrap()),
+            Err(ReplayError::InvalidArena(_))
+        ));
+    }
+}
+
+#[test]
+fn a_replay_with_an_invalid_config_is_not_read() {
+    let mut replay = recorded(Vec::new());
+    replay.config.ball_speed = 0.;
+    assert!(matches!(
+        Replay::decode(&replay.encode().unwrap()),
+        Err(ReplayError::InvalidConfig(_))
+    ));
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Characters in a level layout that leave the cell empty.
+const EMPTY_CELLS: [char; 2] = [' ', '.'];
+
+/// A brick layout loaded from a `.level.ron` file.
+///
+/// `layout` is an ASCII grid where every character is one cell of the brick
+/// wall. The first line is the row against the far wall, and each character
+/// is looked up in `bricks` to decide what goes in that cell. The grid is
+/// stretched to fill the part of the arena set aside by `BrickGrid`, so cells
+/// are sized relative to the arena rather than in pixels.
+#[derive(Asset, TypePath, Debug, Deserialize)]
+pub struct Level {
+    pub bricks: HashMap<char, BrickKind>,
+    pub layout: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BrickKind {
+    /// Number of hits it takes to destroy the brick.
+    #[serde(default = "default_hit_points")]
+    pub hit_points: u32,
+    /// Points awarded when the brick is destroyed.
+    #[serde(default = "default_points")]
+    pub points: usize,
+    /// Unbreakable bricks still bounce the ball, but don't have to be cleared
+    /// to finish the level.
+    #[serde(default = "default_breakable")]
+    pub breakable: bool,
+    /// Red, green and blue in the `0.0..=1.0` range.
+    pub color: (f32, f32, f32),
+}
+
+fn default_hit_points() -> u32 {
+    1
+}
+
+fn default_points() -> usize {
+    1
+}
+
+fn default_breakable() -> bool {
+    true
+}
+
+impl Level {
+    pub fn rows(&self) -> usize {
+        self.layout.len()
+    }
+
+    pub fn columns(&self) -> usize {
+        self.layout
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Iterates over the filled cells as `(row, column, kind)`.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, &BrickKind)> {
+        self.layout.iter().enumerate().flat_map(move |(row, line)| {
+            line.chars()
+                .enumerate()
+                .filter_map(move |(column, c)| self.bricks.get(&c).map(|kind| (row, column, kind)))
+        })
+    }
+
+    /// Parses a level from the contents of a `.level.ron` file and checks
+    /// that it can be played.
+    pub fn from_ron(bytes: &[u8]) -> Result<Level, LevelLoaderError> {
+        let level: Level = ron::de::from_bytes(bytes)?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    fn validate(&self) -> Result<(), LevelLoaderError> {
+        for (row, line) in self.layout.iter().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                if !EMPTY_CELLS.contains(&c) && !self.bricks.contains_key(&c) {
+                    return Err(LevelLoaderError::UnknownBrick { c, row, column });
+                }
+            }
+        }
+        for (c, kind) in self.bricks.iter() {
+            if kind.breakable && kind.hit_points == 0 {
+                return Err(LevelLoaderError::NoHitPoints(*c));
+            }
+        }
+        if !self.cells().any(|(_, _, kind)| kind.breakable) {
+            return Err(LevelLoaderError::NothingToBreak);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LevelLoaderError {
+    #[error("could not read level file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse level file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("unknown brick '{c}' at row {row}, column {column}")]
+    UnknownBrick { c: char, row: usize, column: usize },
+    #[error("breakable brick '{0}' needs at least one hit point")]
+    NoHitPoints(char),
+    #[error("level has no breakable bricks")]
+    NothingToBreak,
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Level::from_ron(&bytes)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
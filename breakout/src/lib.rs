@@ -0,0 +1,752 @@
+//! Breakout: clear the bricks from a series of levels without letting the
+//! ball past the paddle.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use arcade_core::{
+    spawn_ball, spawn_menu, spawn_paddle, ArcadePlugin, Arena, ArenaLayout, ArenaSetup, Ball,
+    BallCollision, BrickDestroyed, Collider, GameConfig, GameEvents, GameRng, GameState,
+    GoalScored, Hud, HudRegion, InputMap, InputSet, MatchEnded, MenuAction, Paddle, PaddleInputs,
+    PhysicsSet, Player, PlayerBindings, PlayerControls, Replay, ReplayMode, Score, ServeStarted,
+    Side, Velocity,
+};
+use bevy::{
+    app::AppExit,
+    asset::LoadState,
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+use level::LevelLoader;
+use rand::Rng;
+
+mod level;
+
+pub use level::{BrickKind, Level, LevelLoaderError};
+
+const STARTING_LIVES: usize = 3;
+
+/// Distance from the paddle's wall to the far one, in the logical units the
+/// game is simulated in.
+pub const ARENA_LENGTH: f32 = 640.;
+/// Width of the arena across the paddle's wall.
+pub const ARENA_BREADTH: f32 = 480.;
+
+/// Simulation ticks per second, unless the plugin is given another rate.
+pub const TICK_RATE: f64 = 120.;
+
+pub struct BreakoutPlugin {
+    pub bricks: BrickGrid,
+    pub orientation: Orientation,
+    /// Asset paths of the levels, played in order and looping back to the
+    /// first once the last one is cleared.
+    pub levels: Vec<String>,
+    pub replay: ReplayMode,
+    /// Size of the arena inside the walls. Defaults to `ARENA_LENGTH` from
+    /// the paddle to the far wall by `ARENA_BREADTH` across.
+    pub arena_size: Option<Vec2>,
+    /// Seed for every game, instead of a random one each time.
+    pub seed: Option<u64>,
+    /// Simulation ticks per second, if not `TICK_RATE`.
+    pub tick_rate: Option<f64>,
+    /// Asset path of the config file, if not `breakout.config.ron`.
+    pub config_path: Option<String>,
+    /// File the player's bindings are kept in.
+    pub controls_path: Option<PathBuf>,
+}
+
+impl Default for BreakoutPlugin {
+    fn default() -> Self {
+        BreakoutPlugin {
+            bricks: BrickGrid::default(),
+            orientation: Orientation::default(),
+            levels: vec![
+                "levels/01.level.ron".to_string(),
+                "levels/02.level.ron".to_string(),
+                "levels/03.level.ron".to_string(),
+            ],
+            replay: ReplayMode::default(),
+            arena_size: None,
+            seed: None,
+            tick_rate: None,
+            config_path: None,
+            controls_path: None,
+        }
+    }
+}
+
+impl BreakoutPlugin {
+    /// Sets the game up the way `replay` was recorded, failing if any of its
+    /// level files have changed since, as the bricks wouldn't be where they
+    /// were.
+    pub fn replaying(replay: &Replay) -> Result<Self, String> {
+        let unreadable = |what| format!("the replay's {what} can't be read");
+        let mut lines = replay.setup.lines();
+        let orientation = lines.next().unwrap_or_default().parse()?;
+        let bricks = lines
+            .next()
+            .and_then(BrickGrid::from_setup)
+            .ok_or_else(|| unreadable("brick grid"))?;
+        let mut levels = Vec::new();
+        for line in lines {
+            let (fingerprint, path) = line
+                .strip_prefix("level ")
+                .and_then(|level| level.split_once(' '))
+                .ok_or_else(|| unreadable("levels"))?;
+            if fingerprint != level_fingerprint(path) {
+                return Err(format!("{path} has changed since the replay was recorded"));
+            }
+            levels.push(path.to_string());
+        }
+        if levels.is_empty() {
+            return Err(unreadable("levels"));
+        }
+        Ok(BreakoutPlugin {
+            bricks,
+            orientation,
+            levels,
+            replay: ReplayMode::Playback(replay.clone()),
+            ..Default::default()
+        })
+    }
+
+    /// What replays keep of how the game was set up, one thing to a line: the
+    /// orientation, the brick grid, then each level with a fingerprint of its
+    /// file.
+    fn replay_setup(&self) -> String {
+        let mut setup = format!("{}\n{}", self.orientation, self.bricks.to_setup());
+        for path in &self.levels {
+            setup += &format!("\nlevel {} {path}", level_fingerprint(path));
+        }
+        setup
+    }
+}
+
+/// A fingerprint of the level file at asset path `path`, or `missing` if it
+/// can't be read.
+fn level_fingerprint(path: &str) -> String {
+    match fs::read(arcade_core::config::asset_file(path)) {
+        // FNV-1a, which unlike the standard hasher is the same on every build
+        Ok(bytes) => {
+            let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+            });
+            format!("{hash:016x}")
+        }
+        Err(_) => "missing".to_string(),
+    }
+}
+
+/// Balls left before the game is over.
+#[derive(Resource)]
+pub struct Lives(pub usize);
+
+/// Which wall the paddle defends. The bricks sit against the opposite one,
+/// and the ball travels between the two.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Orientation {
+    /// Paddle along the bottom wall, moving left and right.
+    #[default]
+    Bottom,
+    /// Paddle along the left wall, moving up and down.
+    Left,
+}
+
+impl Orientation {
+    /// Splits a world-space vector into its component along the play axis
+    /// (pointing from the paddle towards the bricks) and across it.
+    fn split(self, v: Vec2) -> (f32, f32) {
+        match self {
+            Orientation::Bottom => (v.y, v.x),
+            Orientation::Left => (v.x, v.y),
+        }
+    }
+
+    /// The inverse of `split`.
+    fn join(self, along: f32, across: f32) -> Vec2 {
+        match self {
+            Orientation::Bottom => Vec2::new(across, along),
+            Orientation::Left => Vec2::new(along, across),
+        }
+    }
+
+    /// The open side behind the paddle, which the ball is lost through.
+    fn open_sides(self) -> &'static [Side] {
+        match self {
+            Orientation::Bottom => &[Side::Bottom],
+            Orientation::Left => &[Side::Left],
+        }
+    }
+}
+
+impl FromStr for Orientation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bottom" => Ok(Orientation::Bottom),
+            "left" => Ok(Orientation::Left),
+            _ => Err(format!(
+                "unknown orientation '{s}', expected bottom or left"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Orientation::Bottom => "bottom",
+            Orientation::Left => "left",
+        })
+    }
+}
+
+/// How a level's brick layout is fitted into the far end of the arena.
+#[derive(Resource, Clone)]
+pub struct BrickGrid {
+    /// Space between neighbouring bricks.
+    pub gap: f32,
+    /// Space between the far wall and the first row of bricks.
+    pub margin: f32,
+    /// Fraction of the arena's length taken up by the wall.
+    pub depth: f32,
+}
+
+impl Default for BrickGrid {
+    fn default() -> Self {
+        BrickGrid {
+            gap: 4.,
+            margin: 20.,
+            depth: 0.3,
+        }
+    }
+}
+
+impl BrickGrid {
+    fn to_setup(&self) -> String {
+        format!("grid {} {} {}", self.gap, self.margin, self.depth)
+    }
+
+    fn from_setup(line: &str) -> Option<Self> {
+        let mut values = line.strip_prefix("grid ")?.split(' ').map(str::parse);
+        let grid = BrickGrid {
+            gap: values.next()?.ok()?,
+            margin: values.next()?.ok()?,
+            depth: values.next()?.ok()?,
+        };
+        values.next().is_none().then_some(grid)
+    }
+}
+
+#[derive(Resource)]
+struct Levels {
+    paths: Vec<String>,
+    handles: Vec<Handle<Level>>,
+    current: usize,
+    /// Whether the bricks of the current level are in the arena yet.
+    spawned: bool,
+}
+
+#[derive(Component)]
+struct LivesText;
+
+#[derive(Component)]
+struct LevelText;
+
+#[derive(Component)]
+pub struct Brick {
+    hit_points: u32,
+    points: usize,
+    breakable: bool,
+}
+
+fn setup_paddle(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    arena: Res<Arena>,
+    config: Res<GameConfig>,
+    orientation: Res<Orientation>,
+) {
+    let size = orientation.join(config.paddle_width, config.paddle_height);
+    let position = orientation.join(paddle_along(&arena, &config, *orientation), 0.);
+    spawn_paddle(&mut commands, &mut meshes, &mut materials, position, size);
+}
+
+/// Where the paddle sits along the arena, in front of the open wall.
+fn paddle_along(arena: &Arena, config: &GameConfig, orientation: Orientation) -> f32 {
+    let paddle_padding = 10.;
+    let (length, _) = orientation.split(Vec2::new(arena.width, arena.height));
+    -length / 2. + config.paddle_width / 2. + paddle_padding
+}
+
+fn load_levels(asset_server: Res<AssetServer>, mut levels: ResMut<Levels>) {
+    levels.handles = levels
+        .paths
+        .iter()
+        .map(|path| asset_server.load(path.clone()))
+        .collect();
+}
+
+fn spawn_level_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    arena: Res<Arena>,
+    grid: Res<BrickGrid>,
+    orientation: Res<Orientation>,
+    mut levels: ResMut<Levels>,
+    level_assets: Res<Assets<Level>>,
+    asset_server: Res<AssetServer>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if levels.spawned {
+        return;
+    }
+    // move past levels that failed to load, rather than leave the arena
+    // without bricks, and give up if none of them loaded
+    let failed =
+        |handle: &Handle<Level>| asset_server.get_load_state(handle) == Some(LoadState::Failed);
+    if levels.handles.iter().all(failed) {
+        error!("none of the levels could be loaded, so there's nothing to play");
+        exit.send(AppExit);
+        return;
+    }
+    while failed(&levels.handles[levels.current]) {
+        error!(
+            "skipping {}, which failed to load",
+            levels.paths[levels.current]
+        );
+        levels.current = (levels.current + 1) % levels.handles.len();
+    }
+    let Some(level) = levels
+        .handles
+        .get(levels.current)
+        .and_then(|handle| level_assets.get(handle))
+    else {
+        return;
+    };
+    let (rows, columns) = (level.rows(), level.columns());
+
+    // the wall sits against the far wall, opposite the paddle
+    let (length, breadth) = orientation.split(Vec2::new(arena.width, arena.height));
+    let wall_depth = length * grid.depth - grid.margin;
+    let brick_depth = (wall_depth - grid.gap * (rows - 1) as f32) / rows as f32;
+    let brick_breadth = (breadth - grid.gap * (columns + 1) as f32) / columns as f32;
+    let size = orientation.join(brick_depth, brick_breadth);
+    let mesh = Mesh2dHandle(meshes.add(Rectangle::new(size.x, size.y)));
+    let mut brick_materials = HashMap::new();
+
+    for (row, column, kind) in level.cells() {
+        let (red, green, blue) = kind.color;
+        let material = brick_materials
+            .entry((red.to_bits(), green.to_bits(), blue.to_bits()))
+            .or_insert_with(|| materials.add(Color::rgb(red, green, blue)))
+            .clone();
+        let along = length / 2. - grid.margin - brick_depth / 2.
+            - row as f32 * (brick_depth + grid.gap);
+        let across = -breadth / 2. + grid.gap + brick_breadth / 2.
+            + column as f32 * (brick_breadth + grid.gap);
+        let position = orientation.join(along, across);
+        commands.spawn((
+            Brick {
+                hit_points: kind.hit_points,
+                points: kind.points,
+                breakable: kind.breakable,
+            },
+            Collider { size },
+            MaterialMesh2dBundle {
+                mesh: mesh.clone(),
+                material,
+                transform: Transform::from_translation(position.extend(0.)),
+                ..Default::default()
+            },
+        ));
+    }
+    levels.spawned = true;
+}
+
+/// Moves on to the next level once every breakable brick is gone, clearing
+/// out whatever unbreakable bricks are left and re-serving the ball.
+fn level_complete_system(
+    mut commands: Commands,
+    bricks: Query<(Entity, &Brick)>,
+    mut balls: Query<(Entity, &mut Transform, &mut Velocity), With<Ball>>,
+    mut levels: ResMut<Levels>,
+    config: Res<GameConfig>,
+    orientation: Res<Orientation>,
+    mut serves: GameEvents<ServeStarted>,
+) {
+    if !levels.spawned || bricks.iter().any(|(_, brick)| brick.breakable) {
+        return;
+    }
+    for (entity, _) in bricks.iter() {
+        commands.entity(entity).despawn();
+    }
+    for (ball, mut transform, mut velocity) in balls.iter_mut() {
+        serve(
+            ball,
+            &mut transform,
+            &mut velocity,
+            &config,
+            *orientation,
+            &mut serves,
+        );
+    }
+    levels.current = (levels.current + 1) % levels.handles.len();
+    levels.spawned = false;
+}
+
+fn setup_ball(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<GameConfig>,
+    orientation: Res<Orientation>,
+    mut rng: ResMut<GameRng>,
+) {
+    let velocity = match *orientation {
+        // serve straight up at the bricks
+        Orientation::Bottom => Vec2::new(0., config.ball_speed),
+        Orientation::Left => {
+            if rng.gen() {
+                Vec2::new(config.ball_speed, 0.)
+            } else {
+                Vec2::new(-config.ball_speed, 0.)
+            }
+        }
+    };
+
+    spawn_ball(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        config.ball_radius,
+        velocity,
+    );
+}
+
+/// Puts the lives left above the right of the arena, and the level above its
+/// middle.
+fn setup_hud_text(mut commands: Commands, hud: Res<Hud>, lives: Res<Lives>) {
+    let style = TextStyle {
+        font_size: 30.0,
+        color: Color::WHITE,
+        ..Default::default()
+    };
+    commands
+        .entity(hud.region(HudRegion::TopRight))
+        .with_children(|region| {
+            region.spawn((
+                LivesText,
+                TextBundle::from_sections([
+                    TextSection::new("Lives: ", style.clone()),
+                    TextSection::new(lives.0.to_string(), style.clone()),
+                ]),
+            ));
+        });
+    commands
+        .entity(hud.region(HudRegion::Top))
+        .with_children(|region| {
+            region.spawn((LevelText, TextBundle::from_section("", style)));
+        });
+}
+
+fn setup_game_over(mut commands: Commands, score: Res<Score>) {
+    spawn_menu(
+        &mut commands,
+        "GAME OVER",
+        Some(format!("Score: {}", score.player1)),
+        &[
+            ("Play again", MenuAction::Play),
+            ("Main menu", MenuAction::MainMenu),
+            ("Quit", MenuAction::Quit),
+        ],
+    );
+}
+
+/// Puts the score, lives, level, paddle and ball back to where a new game
+/// starts.
+fn reset_game(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut lives: ResMut<Lives>,
+    mut levels: ResMut<Levels>,
+    bricks: Query<Entity, With<Brick>>,
+    mut balls: Query<(Entity, &mut Transform, &mut Velocity), With<Ball>>,
+    mut paddles: Query<&mut Transform, (With<Paddle>, Without<Ball>)>,
+    config: Res<GameConfig>,
+    orientation: Res<Orientation>,
+    mut serves: GameEvents<ServeStarted>,
+) {
+    score.player1 = 0;
+    for mut transform in paddles.iter_mut() {
+        let (along, _) = orientation.split(transform.translation.truncate());
+        let position = orientation.join(along, 0.);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+    lives.0 = STARTING_LIVES;
+    for entity in bricks.iter() {
+        commands.entity(entity).despawn();
+    }
+    levels.current = 0;
+    levels.spawned = false;
+    for (ball, mut transform, mut velocity) in balls.iter_mut() {
+        serve(
+            ball,
+            &mut transform,
+            &mut velocity,
+            &config,
+            *orientation,
+            &mut serves,
+        );
+    }
+}
+
+/// Puts the ball back in the middle, heading straight for the bricks.
+fn serve(
+    ball: Entity,
+    transform: &mut Transform,
+    velocity: &mut Velocity,
+    config: &GameConfig,
+    orientation: Orientation,
+    serves: &mut GameEvents<ServeStarted>,
+) {
+    transform.translation = Vec3::new(0., 0., 0.);
+    *velocity = Velocity::from(orientation.join(config.ball_speed, 0.));
+    serves.send(ServeStarted {
+        ball,
+        server: Player::One,
+        point: Vec2::ZERO,
+        speed: config.ball_speed,
+    });
+}
+
+/// Loses a life once the ball gets past the paddle. Bouncing off the walls,
+/// paddle and bricks is left to the physics step.
+fn ball_move_system(
+    mut balls: Query<(Entity, &mut Transform, &mut Velocity), With<Ball>>,
+    arena: Res<Arena>,
+    config: Res<GameConfig>,
+    orientation: Res<Orientation>,
+    mut lives: ResMut<Lives>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut goals: GameEvents<GoalScored>,
+    mut serves: GameEvents<ServeStarted>,
+    mut ended: GameEvents<MatchEnded>,
+) {
+    let (length, _) = orientation.split(Vec2::new(arena.width, arena.height));
+
+    for (ball, mut transform, mut velocity) in balls.iter_mut() {
+        let (along, _) = orientation.split(transform.translation.truncate());
+        if along - config.ball_radius <= -length / 2. {
+            goals.send(GoalScored {
+                ball,
+                scorer: None,
+                side: orientation.open_sides()[0],
+                point: transform.translation.truncate(),
+                speed: velocity.speed,
+            });
+            lives.0 = lives.0.saturating_sub(1);
+            if lives.0 == 0 {
+                next_state.set(GameState::GameOver);
+                ended.send(MatchEnded { winner: None });
+            }
+            // serve again at the starting speed, away from the paddle
+            transform.translation = Vec3::new(0., 0., 0.);
+            let (along, across) = orientation.split(velocity.direction);
+            *velocity = Velocity::new(config.ball_speed, orientation.join(along.abs(), across));
+            serves.send(ServeStarted {
+                ball,
+                server: Player::One,
+                point: Vec2::ZERO,
+                speed: config.ball_speed,
+            });
+        }
+    }
+}
+
+/// Damages the bricks the ball bounced off during the physics step.
+fn brick_collision_system(
+    mut commands: Commands,
+    mut collisions: EventReader<BallCollision>,
+    mut bricks: Query<&mut Brick>,
+    balls: Query<&Velocity, With<Ball>>,
+    mut score: ResMut<Score>,
+    mut destroyed: GameEvents<BrickDestroyed>,
+) {
+    for collision in collisions.read() {
+        let Ok(mut brick) = bricks.get_mut(collision.other) else {
+            continue;
+        };
+        // a brick can be hit twice in one tick, but only breaks once
+        if !brick.breakable || brick.hit_points == 0 {
+            continue;
+        }
+        brick.hit_points -= 1;
+        if brick.hit_points == 0 {
+            commands.entity(collision.other).despawn();
+            score.player1 += brick.points;
+            destroyed.send(BrickDestroyed {
+                ball: collision.ball,
+                brick: collision.other,
+                point: collision.point,
+                speed: balls
+                    .get(collision.ball)
+                    .map_or(0., |velocity| velocity.speed),
+                points: brick.points,
+            });
+        }
+    }
+}
+
+/// Bindings for A/D and the stick's x axis, or W/S and its y axis when the
+/// paddle is on its side. Moving up moves the paddle towards positive x or y.
+fn default_controls(orientation: Orientation) -> InputMap {
+    let (increase, decrease, axis) = match orientation {
+        Orientation::Bottom => (KeyCode::KeyD, KeyCode::KeyA, GamepadAxisType::LeftStickX),
+        Orientation::Left => (KeyCode::KeyW, KeyCode::KeyS, GamepadAxisType::LeftStickY),
+    };
+    InputMap {
+        player1: PlayerBindings::new(0, increase, decrease, KeyCode::Space, axis),
+        ..Default::default()
+    }
+}
+
+fn controls_input_system(
+    controls: PlayerControls,
+    orientation: Res<Orientation>,
+    mut inputs: ResMut<PaddleInputs>,
+) {
+    inputs.player1 = controls.paddle_input(Player::One, |point| Some(orientation.split(point).1));
+}
+
+fn move_paddle_system(
+    time: Res<Time>,
+    inputs: Res<PaddleInputs>,
+    mut query: Query<&mut Transform, With<Paddle>>,
+    arena: Res<Arena>,
+    config: Res<GameConfig>,
+    orientation: Res<Orientation>,
+) {
+    let (_, breadth) = orientation.split(Vec2::new(arena.width, arena.height));
+
+    for mut transform in query.iter_mut() {
+        let (along, across) = orientation.split(transform.translation.truncate());
+        let across = inputs
+            .player1
+            .next_position(across, &config, time.delta_seconds())
+            .clamp(
+                -breadth / 2. + config.paddle_height / 2.,
+                breadth / 2. - config.paddle_height / 2.,
+            );
+        let position = orientation.join(along, across);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+fn lives_text_update_system(mut query: Query<&mut Text, With<LivesText>>, lives: Res<Lives>) {
+    for mut text in query.iter_mut() {
+        text.sections[1].value = lives.0.to_string();
+    }
+}
+
+fn level_text_update_system(mut query: Query<&mut Text, With<LevelText>>, levels: Res<Levels>) {
+    let value = format!("Level {}", levels.current + 1);
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+impl Plugin for BreakoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ArcadePlugin {
+            title: "BREAKOUT",
+            arena: ArenaLayout {
+                size: self
+                    .arena_size
+                    .unwrap_or_else(|| self.orientation.join(ARENA_LENGTH, ARENA_BREADTH)),
+                open_sides: self.orientation.open_sides(),
+            },
+            config: GameConfig {
+                arena_width_fraction: 0.6,
+                arena_height_fraction: 0.9,
+                ..Default::default()
+            },
+            config_path: self.config_path.clone(),
+            seed: self.seed,
+            players: 1,
+            tick_rate: self.tick_rate.unwrap_or(TICK_RATE),
+            replay: self.replay.clone(),
+            controls: default_controls(self.orientation),
+            controls_path: self.controls_path.clone(),
+            setup: self.replay_setup(),
+        });
+        app.insert_resource(Lives(STARTING_LIVES));
+        app.insert_resource(self.bricks.clone());
+        app.insert_resource(self.orientation);
+        app.insert_resource(Levels {
+            paths: self.levels.clone(),
+            handles: Vec::new(),
+            current: 0,
+            spawned: false,
+        });
+        app.init_asset::<Level>();
+        app.init_asset_loader::<LevelLoader>();
+        app.add_systems(
+            Startup,
+            (
+                setup_ball,
+                load_levels,
+                (setup_paddle, setup_hud_text).after(ArenaSetup),
+            ),
+        );
+        app.add_systems(
+            Update,
+            (
+                lives_text_update_system,
+                level_text_update_system,
+                // bricks go up as soon as the level loads, even behind a menu
+                spawn_level_system,
+            ),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (
+                controls_input_system.in_set(InputSet::Read),
+                // spawn the next level on the tick after the last one is
+                // cleared, however the ticks fall across frames, so replays
+                // see the bricks at the same time
+                (
+                    spawn_level_system.before(ball_move_system),
+                    ball_move_system,
+                    move_paddle_system.after(InputSet::Replay),
+                )
+                    .before(PhysicsSet),
+                (brick_collision_system, level_complete_system)
+                    .chain()
+                    .after(PhysicsSet),
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+        app.add_systems(OnEnter(GameState::GameOver), setup_game_over);
+        // a new game starts from the main menu or after a game over
+        for from in [GameState::MainMenu, GameState::GameOver] {
+            app.add_systems(
+                OnTransition {
+                    from,
+                    to: GameState::Countdown,
+                },
+                reset_game,
+            );
+        }
+    }
+}
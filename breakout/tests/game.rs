@@ -0,0 +1,240 @@
+use std::fs;
+use std::time::Duration;
+
+use arcade_core::testing::TestApp;
+use arcade_core::{
+    Ball, GameState, GoalScored, Paddle, PaddleInput, PaddleInputs, Replay, ReplayMode, Score,
+    ServeStarted, Side, Velocity, Wall,
+};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use breakout::{BreakoutPlugin, Brick, Level, Lives, Orientation};
+
+const ARENA: Vec2 = Vec2::new(480., 640.);
+
+fn breakout() -> TestApp {
+    start(BreakoutPlugin {
+        arena_size: Some(ARENA),
+        ..Default::default()
+    })
+}
+
+/// Starts a game once the first level's bricks are in.
+fn start(game: BreakoutPlugin) -> TestApp {
+    let mut app = TestApp::new(game);
+    let loaded = app.wait_for(Duration::from_secs(10), |world| {
+        world.query::<&Brick>().iter(world).next().is_some()
+    });
+    assert!(loaded, "the first level never loaded");
+    app.start_match();
+    app
+}
+
+#[test]
+fn a_level_that_fails_to_load_is_skipped() {
+    let mut app = TestApp::new(BreakoutPlugin {
+        arena_size: Some(ARENA),
+        levels: vec![
+            "levels/missing.level.ron".to_string(),
+            "levels/01.level.ron".to_string(),
+        ],
+        ..Default::default()
+    });
+    let loaded = app.wait_for(Duration::from_secs(10), |world| {
+        world.query::<&Brick>().iter(world).next().is_some()
+    });
+    assert!(loaded, "the second level never took the first one's place");
+}
+
+#[test]
+fn the_game_stops_when_no_level_loads() {
+    let mut app = TestApp::new(BreakoutPlugin {
+        arena_size: Some(ARENA),
+        levels: vec!["levels/missing.level.ron".to_string()],
+        ..Default::default()
+    });
+    let stopped = app.wait_for(Duration::from_secs(10), |world| {
+        !world.resource::<Events<AppExit>>().is_empty()
+    });
+    assert!(stopped, "the game carried on without any bricks");
+}
+
+#[test]
+fn clearing_a_level_brings_in_the_next() {
+    let mut app = breakout();
+    let mut bricks = app.world_mut().query_filtered::<Entity, With<Brick>>();
+    let bricks: Vec<_> = bricks.iter(app.world()).collect();
+    for brick in bricks {
+        app.world_mut().despawn(brick);
+    }
+    app.run_ticks(1);
+
+    let file = arcade_core::config::asset_file("levels/02.level.ron");
+    let next = Level::from_ron(&fs::read(file).unwrap()).unwrap();
+    assert_eq!(app.count::<With<Brick>>(), next.cells().count());
+    let mut texts = app.world_mut().query::<&Text>();
+    let shown = texts
+        .iter(app.world())
+        .any(|text| text.sections[0].value == "Level 2");
+    assert!(shown, "the HUD should say it's the second level");
+}
+
+#[test]
+fn spawns_the_arena_ball_paddle_and_bricks() {
+    let mut app = breakout();
+    assert_eq!(app.count::<With<Wall>>(), 4);
+    assert_eq!(app.count::<With<Ball>>(), 1);
+    assert_eq!(app.count::<With<Paddle>>(), 1);
+    assert!(app.count::<With<Brick>>() > 0);
+}
+
+#[test]
+fn ball_breaks_bricks_for_points() {
+    let mut app = breakout();
+    let bricks = app.count::<With<Brick>>();
+    app.run_ticks(1500);
+
+    assert!(app.score().player1 > 0);
+    assert!(app.count::<With<Brick>>() < bricks);
+    assert_eq!(app.world().resource::<Lives>().0, 3);
+}
+
+#[test]
+fn missing_the_ball_costs_lives_until_the_game_is_over() {
+    let mut app = breakout();
+    // hold the paddle off to the side, away from the ball
+    app.hold_inputs(PaddleInputs {
+        player1: PaddleInput::DOWN,
+        ..Default::default()
+    });
+
+    let over = app.run_until(10_000, |world| {
+        *world.resource::<State<GameState>>().get() == GameState::GameOver
+    });
+    assert!(over);
+    assert_eq!(app.world().resource::<Lives>().0, 0);
+}
+
+/// Where the ball and paddle are, and what's left of the bricks and lives.
+fn game_state(app: &mut TestApp) -> (Vec2, Vec3, usize, Score, usize) {
+    let mut paddles = app.world_mut().query_filtered::<&Transform, With<Paddle>>();
+    let paddle = paddles.single(app.world()).translation;
+    (
+        app.ball_position(),
+        paddle,
+        app.count::<With<Brick>>(),
+        app.score(),
+        app.world().resource::<Lives>().0,
+    )
+}
+
+#[test]
+fn a_recorded_game_plays_back_the_same() {
+    let dir = std::env::temp_dir().join(format!("breakout-replay-{}", std::process::id()));
+    let mut app = start(BreakoutPlugin {
+        arena_size: Some(ARENA),
+        replay: ReplayMode::Record(dir.clone()),
+        ..Default::default()
+    });
+    // steer one way, then the other
+    let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keys.press(KeyCode::KeyD);
+    app.run_ticks(200);
+    let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keys.release(KeyCode::KeyD);
+    keys.press(KeyCode::KeyA);
+    app.run_ticks(1300);
+    let played = game_state(&mut app);
+    assert_ne!(played.1.x, 0.);
+    // ending the game saves the recording
+    app.set_state(GameState::GameOver);
+    let file = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+    let replay = Replay::load(&file, "breakout").unwrap();
+    let _ = fs::remove_dir_all(dir);
+
+    let mut app = start(BreakoutPlugin::replaying(&replay).unwrap());
+    app.run_ticks(1500);
+    assert_eq!(game_state(&mut app), played);
+}
+
+#[test]
+fn a_replay_of_other_levels_is_not_played_back() {
+    let mut replay = Replay {
+        game: "breakout".to_string(),
+        setup: "bottom\ngrid 4 20 0.3\nlevel 0123456789abcdef levels/01.level.ron".to_string(),
+        seed: 0,
+        arena: ARENA,
+        tick_rate: 120.,
+        config: Default::default(),
+        inputs: Vec::new(),
+    };
+    let err = BreakoutPlugin::replaying(&replay).err().unwrap();
+    assert!(err.contains("levels/01.level.ron has changed"), "{err}");
+
+    replay.setup = "bottom\ngrid 4 20".to_string();
+    assert!(BreakoutPlugin::replaying(&replay).is_err());
+}
+
+/// The `E`s sent on the last tick or two, before they're cleared.
+fn sent<E: Event + Copy>(world: &World) -> Vec<E> {
+    let events = world.resource::<Events<E>>();
+    events.get_reader().read(events).copied().collect()
+}
+
+#[test]
+fn a_new_level_serves_once() {
+    let mut app = breakout();
+    let mut bricks = app.world_mut().query_filtered::<Entity, With<Brick>>();
+    let bricks: Vec<_> = bricks.iter(app.world()).collect();
+    for brick in bricks {
+        app.world_mut().despawn(brick);
+    }
+    app.run_ticks(1);
+    assert_eq!(sent::<ServeStarted>(app.world()).len(), 1);
+}
+
+#[test]
+fn a_new_game_serves_once() {
+    let mut app = breakout();
+    app.run_ticks(100);
+    app.set_state(GameState::GameOver);
+    app.set_state(GameState::Countdown);
+    assert_eq!(sent::<ServeStarted>(app.world()).len(), 1);
+}
+
+#[test]
+fn with_the_paddle_on_the_left_the_ball_is_lost_past_the_left_wall() {
+    let mut app = start(BreakoutPlugin {
+        orientation: Orientation::Left,
+        ..Default::default()
+    });
+    // served from the middle at the bricks on the right
+    let mut balls = app.world_mut().query_filtered::<&Velocity, With<Ball>>();
+    let serve = balls.single(app.world()).direction;
+    assert_eq!(serve, Vec2::X);
+
+    // the paddle moves up and down the left wall
+    let paddle = |app: &mut TestApp| {
+        let mut paddles = app.world_mut().query_filtered::<&Transform, With<Paddle>>();
+        paddles.single(app.world()).translation
+    };
+    let before = paddle(&mut app);
+    assert!(before.x < 0.);
+    app.hold_inputs(PaddleInputs {
+        player1: PaddleInput::UP,
+        ..Default::default()
+    });
+    app.run_ticks(30);
+    let after = paddle(&mut app);
+    assert_eq!(after.x, before.x);
+    assert!(after.y > before.y);
+
+    // and with it out of the way, the ball comes back off the bricks and out
+    // past it
+    let lost = app.run_until(2000, |world| !sent::<GoalScored>(world).is_empty());
+    assert!(lost);
+    let goal = sent::<GoalScored>(app.world())[0];
+    assert_eq!(goal.side, Side::Left);
+    assert!(goal.point.x < before.x);
+    assert_eq!(app.world().resource::<Lives>().0, 2);
+}
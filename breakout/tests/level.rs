@@ -0,0 +1,71 @@
+use breakout::{Level, LevelLoaderError};
+
+#[test]
+fn a_layout_is_read_row_by_row_from_the_far_wall() {
+    let level = Level::from_ron(
+        br#"(
+            bricks: {
+                'R': (color: (0.9, 0.3, 0.3), points: 3),
+                'X': (color: (0.5, 0.5, 0.5), breakable: false),
+            },
+            layout: [
+                "RR.R",
+                " X",
+            ],
+        )"#,
+    )
+    .unwrap();
+
+    assert_eq!((level.rows(), level.columns()), (2, 4));
+    let cells: Vec<_> = level
+        .cells()
+        .map(|(row, column, kind)| (row, column, kind.points, kind.breakable))
+        .collect();
+    assert_eq!(
+        cells,
+        [
+            (0, 0, 3, true),
+            (0, 1, 3, true),
+            (0, 3, 3, true),
+            (1, 1, 1, false),
+        ]
+    );
+    assert!(level.cells().all(|(_, _, kind)| kind.hit_points == 1));
+}
+
+#[test]
+fn an_unknown_brick_is_reported_where_it_is() {
+    let err = Level::from_ron(br#"(bricks: {'R': (color: (1, 0, 0))}, layout: ["RR", "R?"])"#)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        LevelLoaderError::UnknownBrick {
+            c: '?',
+            row: 1,
+            column: 1
+        }
+    ));
+}
+
+#[test]
+fn a_breakable_brick_needs_hit_points() {
+    let err =
+        Level::from_ron(br#"(bricks: {'R': (color: (1, 0, 0), hit_points: 0)}, layout: ["RR"])"#)
+            .unwrap_err();
+    assert!(matches!(err, LevelLoaderError::NoHitPoints('R')));
+}
+
+#[test]
+fn a_level_needs_something_to_break() {
+    let err = Level::from_ron(
+        br#"(bricks: {'X': (color: (1, 1, 1), breakable: false)}, layout: ["XX", ".."])"#,
+    )
+    .unwrap_err();
+    assert!(matches!(err, LevelLoaderError::NothingToBreak));
+}
+
+#[test]
+fn a_file_that_is_not_a_level_fails_to_parse() {
+    let err = Level::from_ron(b"(layout: [\"RR\"])").unwrap_err();
+    assert!(matches!(err, LevelLoaderError::Ron(_)));
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+use std::str::FromStr;
+
+use arcade_core::{Arena, Ball, GameConfig, GameRng, Velocity};
+use bevy::prelude::*;
+use rand::Rng;
+
+/// Who is moving a paddle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Controller {
+    #[default]
+    Human,
+    Computer(Difficulty),
+}
+
+impl FromStr for Controller {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Controller::Human),
+            "easy" => Ok(Controller::Computer(Difficulty::Easy)),
+            "normal" => Ok(Controller::Computer(Difficulty::Normal)),
+            "hard" => Ok(Controller::Computer(Difficulty::Hard)),
+            _ => Err(format!(
+                "unknown controller '{s}', expected one of human, easy, normal, hard"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Controller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Controller::Human => "human",
+            Controller::Computer(Difficulty::Easy) => "easy",
+            Controller::Computer(Difficulty::Normal) => "normal",
+            Controller::Computer(Difficulty::Hard) => "hard",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Seconds between the computer re-reading where the ball is going.
+    fn reaction_delay(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.4,
+            Difficulty::Normal => 0.2,
+            Difficulty::Hard => 0.08,
+        }
+    }
+
+    fn max_speed(self) -> f32 {
+        match self {
+            Difficulty::Easy => 140.,
+            Difficulty::Normal => 200.,
+            Difficulty::Hard => 280.,
+        }
+    }
+
+    /// Largest distance the computer may miss its predicted target by.
+    fn aim_error(self) -> f32 {
+        match self {
+            Difficulty::Easy => 40.,
+            Difficulty::Normal => 20.,
+            Difficulty::Hard => 5.,
+        }
+    }
+}
+
+/// Drives a paddle towards where the ball is predicted to arrive.
+#[derive(Component)]
+pub struct AiPaddle {
+    difficulty: Difficulty,
+    reaction: Timer,
+    target: f32,
+}
+
+impl AiPaddle {
+    pub fn new(difficulty: Difficulty) -> Self {
+        AiPaddle {
+            difficulty,
+            reaction: Timer::from_seconds(difficulty.reaction_delay(), TimerMode::Repeating),
+            target: 0.,
+        }
+    }
+
+    /// Forgets where the ball was going, for the start of a match.
+    pub fn reset(&mut self) {
+        self.reaction.reset();
+        self.target = 0.;
+    }
+}
+
+/// Predicts the height at which a ball of `ball_radius` will reach `x`,
+/// folding the path back on itself for every bounce off the ceiling or floor.
+/// Returns `None` if the ball is moving away from `x`.
+pub fn predict_ball_y(
+    position: Vec2,
+    velocity: Vec2,
+    x: f32,
+    arena_height: f32,
+    ball_radius: f32,
+) -> Option<f32> {
+    if velocity.x == 0. || (x - position.x).signum() != velocity.x.signum() {
+        return None;
+    }
+    let time = (x - position.x) / velocity.x;
+    let y = position.y + velocity.y * time;
+
+    // the ball's centre stays within this band, bouncing at either end
+    let min = -arena_height / 2. + ball_radius;
+    let span = arena_height - ball_radius * 2.;
+    if span <= 0. {
+        return Some(0.);
+    }
+    let folded = (y - min).rem_euclid(span * 2.);
+    if folded > span {
+        Some(min + span * 2. - folded)
+    } else {
+        Some(min + folded)
+    }
+}
+
+pub fn ai_paddle_system(
+    time: Res<Time>,
+    balls: Query<(&Transform, &Velocity), With<Ball>>,
+    mut paddles: Query<(&mut Transform, &mut AiPaddle), Without<Ball>>,
+    arena: Res<Arena>,
+    config: Res<GameConfig>,
+    mut rng: ResMut<GameRng>,
+) {
+    for (mut transform, mut ai) in paddles.iter_mut() {
+        if ai.reaction.tick(time.delta()).just_finished() {
+            // hit the ball with the face of the paddle, not its centre
+            let face = transform.translation.x
+                - transform.translation.x.signum()
+                    * (config.paddle_width / 2. + config.ball_radius);
+            let prediction = balls.iter().find_map(|(ball, velocity)| {
+                predict_ball_y(
+                    ball.translation.truncate(),
+                    velocity.to_vec2(),
+                    face,
+                    arena.height,
+                    config.ball_radius,
+                )
+            });
+            ai.target = match prediction {
+                Some(y) => {
+                    let error = ai.difficulty.aim_error();
+                    y + rng.gen_range(-1.0..=1.0) * error
+                }
+                // drift back to the middle while the ball is heading away
+                None => 0.,
+            };
+        }
+
+        let max_step = ai.difficulty.max_speed() * time.delta_seconds();
+        let step = (ai.target - transform.translation.y).clamp(-max_step, max_step);
+        transform.translation.y = (transform.translation.y + step).clamp(
+            -arena.height / 2. + config.paddle_height / 2.,
+            arena.height / 2. - config.paddle_height / 2.,
+        );
+    }
+}
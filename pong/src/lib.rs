@@ -0,0 +1,280 @@
+//! Two-player pong, against another person on the same keyboard or online, or
+//! against the computer.
+
+use std::path::PathBuf;
+
+use ai::{AiPaddle, Controller};
+use arcade_core::{
+    spawn_ball, spawn_paddle, ArcadePlugin, Arena, ArenaLayout, ArenaSetup, Ball, GameConfig,
+    GameEvents, GameRng, GameState, GoalScored, InputMap, InputSet, Paddle, PaddleInputs,
+    PhysicsSet, Player, Player1, Player2, PlayerControls, ReplayMode, Score, Side, Velocity,
+};
+use bevy::prelude::*;
+use net::{NetConfig, NetPlugin};
+use rand::Rng;
+use rules::{MatchRules, Sets};
+use serve::{Rally, Serve, Server};
+
+pub mod ai;
+pub mod net;
+pub mod rules;
+pub mod serve;
+
+/// Size of the arena inside the walls, in the logical units the game is
+/// simulated in.
+pub const ARENA_SIZE: Vec2 = Vec2::new(800., 400.);
+
+/// Simulation ticks per second, unless the plugin is given another rate.
+pub const TICK_RATE: f64 = 120.;
+
+#[derive(Default)]
+pub struct PongPlugin {
+    pub player1: Controller,
+    pub player2: Controller,
+    pub rules: MatchRules,
+    /// Plays against a peer over the network instead, with both paddles
+    /// controlled by people.
+    pub online: Option<NetConfig>,
+    pub replay: ReplayMode,
+    /// Size of the arena inside the walls, if not `ARENA_SIZE`.
+    pub arena_size: Option<Vec2>,
+    /// Seed for every match, instead of a random one each time.
+    pub seed: Option<u64>,
+    /// Simulation ticks per second, if not `TICK_RATE`.
+    pub tick_rate: Option<f64>,
+    /// Asset path of the config file, if not `pong.config.ron`.
+    pub config_path: Option<String>,
+    /// File the players' bindings are kept in.
+    pub controls_path: Option<PathBuf>,
+}
+
+#[derive(Resource)]
+struct Players {
+    player1: Controller,
+    player2: Controller,
+}
+
+/// How far each paddle sits in front of its goal.
+const PADDLE_PADDING: f32 = 10.;
+
+/// How far either paddle sits from the middle of the arena.
+fn paddle_offset(arena: &Arena, config: &GameConfig) -> f32 {
+    arena.width / 2. - config.paddle_width / 2. - PADDLE_PADDING
+}
+
+fn setup_paddles(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    arena: Res<Arena>,
+    config: Res<GameConfig>,
+    players: Res<Players>,
+) {
+    let size = Vec2::new(config.paddle_width, config.paddle_height);
+    let offset = paddle_offset(&arena, &config);
+    let player1 = spawn_paddle(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        Vec2::new(-offset, 0.),
+        size,
+    )
+    .insert(Player1)
+    .id();
+    let player2 = spawn_paddle(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        Vec2::new(offset, 0.),
+        size,
+    )
+    .insert(Player2)
+    .id();
+
+    for (entity, controller) in [(player1, players.player1), (player2, players.player2)] {
+        if let Controller::Computer(difficulty) = controller {
+            commands.entity(entity).insert(AiPaddle::new(difficulty));
+        }
+    }
+}
+
+fn setup_ball(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<GameConfig>,
+    mut rng: ResMut<GameRng>,
+) {
+    let velocity = if rng.gen() {
+        config.ball_speed
+    } else {
+        -config.ball_speed
+    };
+    spawn_ball(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        config.ball_radius,
+        Vec2::new(velocity, 0.),
+    );
+}
+
+/// Scores once the ball is through a goal and hands over the serve. Bouncing
+/// off the paddles, ceiling and floor is left to the physics step.
+fn ball_move_system(
+    balls: Query<(Entity, &Transform, &Velocity), With<Ball>>,
+    arena: Res<Arena>,
+    config: Res<GameConfig>,
+    rules: Res<MatchRules>,
+    mut score: ResMut<Score>,
+    mut serve: ResMut<Serve>,
+    mut goals: GameEvents<GoalScored>,
+) {
+    for (ball, transform, velocity) in balls.iter() {
+        let (winner, side) = if transform.translation.x + config.ball_radius >= arena.width / 2. {
+            score.player1 += 1;
+            (Server::Player1, Side::Right)
+        } else if transform.translation.x - config.ball_radius <= -arena.width / 2. {
+            score.player2 += 1;
+            (Server::Player2, Side::Left)
+        } else {
+            continue;
+        };
+        serve.after_point(rules.serve, winner);
+        goals.send(GoalScored {
+            ball,
+            scorer: Some(winner.player()),
+            side,
+            point: transform.translation.truncate(),
+            speed: velocity.speed,
+        });
+    }
+}
+
+/// Reads each player's bindings, by default W/S for player 1 and the arrow
+/// keys for player 2 so two people can share one keyboard, or a gamepad
+/// each. A touch steers the paddle on its half of the screen.
+fn controls_input_system(controls: PlayerControls, mut inputs: ResMut<PaddleInputs>) {
+    inputs.player1 = controls.paddle_input(Player::One, |point| (point.x < 0.).then_some(point.y));
+    inputs.player2 = controls.paddle_input(Player::Two, |point| (point.x >= 0.).then_some(point.y));
+}
+
+fn move_paddle_system(
+    time: Res<Time>,
+    inputs: Res<PaddleInputs>,
+    mut paddles: Query<(&mut Transform, Has<Player1>), (With<Paddle>, Without<AiPaddle>)>,
+    arena: Res<Arena>,
+    config: Res<GameConfig>,
+) {
+    for (mut transform, is_player1) in paddles.iter_mut() {
+        let input = if is_player1 {
+            inputs.player1
+        } else {
+            inputs.player2
+        };
+        let y = input.next_position(transform.translation.y, &config, time.delta_seconds());
+        transform.translation.y = y.clamp(
+            -arena.height / 2. + config.paddle_height / 2.,
+            arena.height / 2. - config.paddle_height / 2.,
+        );
+    }
+}
+
+impl Plugin for PongPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ArcadePlugin {
+            title: "PONG",
+            arena: ArenaLayout {
+                size: self.arena_size.unwrap_or(ARENA_SIZE),
+                open_sides: &[Side::Left, Side::Right],
+            },
+            config: GameConfig {
+                arena_width_fraction: 0.9,
+                arena_height_fraction: 0.8,
+                ..Default::default()
+            },
+            config_path: self.config_path.clone(),
+            seed: self.seed,
+            players: 2,
+            tick_rate: self.tick_rate.unwrap_or(TICK_RATE),
+            replay: self.replay.clone(),
+            controls: InputMap::default(),
+            controls_path: self.controls_path.clone(),
+            setup: format!(
+                "{} {}\n{}",
+                self.player1,
+                self.player2,
+                self.rules.to_setup()
+            ),
+        });
+        app.insert_resource(Players {
+            player1: self.player1,
+            player2: self.player2,
+        });
+        app.insert_resource(self.rules);
+        app.init_resource::<Sets>();
+        app.init_resource::<Serve>();
+        app.init_resource::<Rally>();
+        app.add_systems(
+            Startup,
+            (
+                setup_ball,
+                (
+                    setup_paddles,
+                    rules::setup_sets_text,
+                    serve::setup_serve_text,
+                    serve::setup_rally_text,
+                )
+                    .after(ArenaSetup),
+            ),
+        );
+        app.add_systems(
+            Update,
+            (
+                rules::sets_text_update_system,
+                serve::serve_text_update_system,
+                serve::rally_text_update_system,
+            ),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (
+                (ball_move_system, rules::match_rules_system).chain(),
+                move_paddle_system.after(InputSet::Replay),
+                ai::ai_paddle_system,
+                // hold the ball against the paddles where they've moved to
+                serve::serve_system
+                    .after(rules::match_rules_system)
+                    .after(move_paddle_system)
+                    .after(ai::ai_paddle_system),
+            )
+                .before(PhysicsSet)
+                .run_if(in_state(GameState::Playing)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            serve::count_rally_system
+                .after(PhysicsSet)
+                .run_if(in_state(GameState::Playing)),
+        );
+        match self.online {
+            Some(config) => {
+                app.add_plugins(NetPlugin { config });
+            }
+            None => {
+                app.add_systems(FixedUpdate, controls_input_system.in_set(InputSet::Read));
+            }
+        }
+        app.add_systems(OnEnter(GameState::GameOver), rules::setup_victory_screen);
+        // a new match starts from the main menu or as a rematch
+        for from in [GameState::MainMenu, GameState::GameOver] {
+            app.add_systems(
+                OnTransition {
+                    from,
+                    to: GameState::Countdown,
+                },
+                rules::reset_match,
+            );
+        }
+    }
+}
@@ -1,521 +1,147 @@
-use bevy::diagnostic::DiagnosticsStore;
-use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
-use bevy::{
-    prelude::*,
-    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
-};
-
-const PADDLE_WIDTH: f32 = 10.0;
-const PADDLE_HEIGHT: f32 = 50.0;
-const BALL_RADIUS: f32 = 7.0;
-const BALL_VELOCITY: f32 = 200.0;
-
-pub struct PongPlugin;
-
-fn main() {
-    App::new()
-        .add_plugins((
-            DefaultPlugins,
-            PongPlugin,
-            FrameTimeDiagnosticsPlugin::default(),
-        ))
-        .run();
-}
-
-#[derive(Resource)]
-struct Arena {
-    width: f32,
-    height: f32,
-    wall_thickness: f32,
-}
-
-#[derive(Resource)]
-struct Score {
-    player1: usize,
-    player2: usize,
-}
-
-#[derive(Component)]
-struct MainCamera;
-
-#[derive(Component)]
-struct FpsRoot;
-
-#[derive(Component)]
-struct FpsText;
-
-#[derive(Component)]
-struct Ball;
-
-#[derive(Component)]
-struct Paddle;
-
-#[derive(Component)]
-struct Player1;
-
-#[derive(Component)]
-struct Player2;
-
-#[derive(Component)]
-struct ScoreText;
-
-#[derive(Component)]
-struct Velocity {
-    x: f32,
-    y: f32,
-}
-
-#[derive(Component)]
-struct Wall;
-
-fn startup(windows: Query<&Window>, mut arena: ResMut<Arena>) {
-    let window = windows.single();
-    let window_width = window.width();
-    let window_height = window.height();
-    let wall_thickness = 4.;
-    let mut arena_width = (window_width - wall_thickness * 2.) * 0.9;
-    let mut arena_height = (window_height - wall_thickness * 2.) * 0.8;
-    if arena_width / arena_height > 2. {
-        arena_width = arena_height * 2.;
-    } else {
-        arena_height = arena_width / 2.;
-    }
-    arena.width = arena_width;
-    arena.height = arena_height;
-    arena.wall_thickness = wall_thickness;
-}
-
-fn setup_arena(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    arena: Res<Arena>,
-) {
-    // Top Wall
-    commands.spawn((
-        Wall,
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(arena.width, arena.wall_thickness))),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_xyz(0., arena.height / 2. + arena.wall_thickness / 2., 0.),
-            ..Default::default()
-        },
-    ));
-
-    // Bottom Wall
-    commands.spawn((
-        Wall,
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(arena.width, arena.wall_thickness))),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_xyz(0., -arena.height / 2. - arena.wall_thickness / 2., 0.),
-            ..Default::default()
-        },
-    ));
-
-    // Left Wall
-    commands.spawn((
-        Wall,
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(arena.wall_thickness, arena.height))),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_xyz(-arena.width / 2. - arena.wall_thickness / 2., 0., 0.),
-            ..Default::default()
-        },
-    ));
-
-    // Right Wall
-    commands.spawn((
-        Wall,
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(arena.wall_thickness, arena.height))),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_xyz(arena.width / 2. + arena.wall_thickness / 2., 0., 0.),
-            ..Default::default()
-        },
-    ));
-}
+use std::net::SocketAddr;
 
-fn setup_paddles(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    arena: Res<Arena>,
-) {
-    let paddle_padding = 10.;
-    commands.spawn((
-        Paddle,
-        Player1,
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(PADDLE_WIDTH, PADDLE_HEIGHT))),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_xyz(
-                (-arena.width / 2. + PADDLE_WIDTH / 2.) + paddle_padding,
-                0.,
-                0.,
-            ),
-            ..Default::default()
-        },
-    ));
-    commands.spawn((
-        Paddle,
-        Player2,
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(PADDLE_WIDTH, PADDLE_HEIGHT))),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_xyz(
-                (arena.width / 2. - PADDLE_WIDTH / 2.) - paddle_padding,
-                0.,
-                0.,
-            ),
-            ..Default::default()
-        },
-    ));
-}
-
-fn setup_ball(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let velocity: f32;
-    if rand::random() {
-        velocity = BALL_VELOCITY;
-    } else {
-        velocity = -BALL_VELOCITY;
-    }
-
-    commands.spawn((
-        Ball,
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(BALL_RADIUS, BALL_RADIUS))),
-            material: materials.add(Color::WHITE),
-            transform: Transform::from_xyz(0., 0., 0.),
-            ..Default::default()
-        },
-        Velocity { x: velocity, y: 0. },
-    ));
-}
-
-fn setup_score(mut commands: Commands, arena: Res<Arena>) {
-    commands.spawn((
-        Player1,
-        ScoreText,
-        TextBundle {
-            text: Text {
-                sections: vec![TextSection {
-                    value: "0".to_string(),
-                    style: TextStyle {
-                        font_size: 50.0,
-                        color: Color::WHITE,
-                        ..Default::default()
-                    },
-                }],
-                ..Default::default()
-            },
-            style: Style {
-                position_type: PositionType::Absolute,
-                top: Val::Px(arena.height / 2. - 50.),
-                left: Val::Px(arena.width / 2. - 50.),
-                ..Default::default()
-            },
-            ..Default::default()
-        },
-    ));
-    commands.spawn((
-        Player2,
-        ScoreText,
-        TextBundle {
-            text: Text {
-                sections: vec![TextSection {
-                    value: "0".to_string(),
-                    style: TextStyle {
-                        font_size: 50.0,
-                        color: Color::WHITE,
-                        ..Default::default()
-                    },
-                }],
-                ..Default::default()
-            },
-            style: Style {
-                position_type: PositionType::Absolute,
-                top: Val::Px(arena.height / 2. - 50.),
-                right: Val::Px(arena.width / 2. - 50.),
-                ..Default::default()
-            },
-            ..Default::default()
-        },
-    ));
-}
-
-fn setup_camera(mut commands: Commands) {
-    commands.spawn((Camera2dBundle::default(), MainCamera));
-}
-
-fn ball_move_system(
-    time: Res<Time>,
-    mut set: ParamSet<(
-        Query<(&mut Transform, &mut Velocity), With<Ball>>,
-        Query<&Transform, With<Paddle>>,
-    )>,
-    arena: Res<Arena>,
-    mut score: ResMut<Score>,
-) {
-    let paddles: Vec<Transform> = set.p1().iter().copied().collect();
-
-    for (mut transform, mut velocity) in set.p0().iter_mut() {
-        // score if ball goes out of bounds and reset ball position
-        if transform.translation.x + BALL_RADIUS >= arena.width / 2. {
-            score.player1 += 1;
-            transform.translation = Vec3::new(0., 0., 0.);
-            velocity.x = -velocity.x;
-        } else if transform.translation.x - BALL_RADIUS <= -arena.width / 2. {
-            score.player2 += 1;
-            transform.translation = Vec3::new(0., 0., 0.);
-            velocity.x = -velocity.x;
+use arcade_core::cli::{Args, CommonArgs, COMMON_USAGE};
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
+use pong::ai::Controller;
+use pong::net::{NetConfig, Seat};
+use pong::rules::MatchRules;
+use pong::PongPlugin;
+
+/// Parses who controls each paddle and the match rules from a replay's
+/// setup, e.g. `human hard` then `rules 11 2 1`. Replays recorded before the
+/// rules were kept are played back with the default ones.
+fn parse_setup(setup: &str) -> Result<(Controller, Controller, MatchRules), String> {
+    let mut lines = setup.lines();
+    let controllers = lines
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<Controller>, _>>()?;
+    let rules = match lines.next() {
+        Some(line) => {
+            MatchRules::from_setup(line).ok_or("the replay's match rules can't be read")?
         }
-
-        // check for collision with paddles
-        for paddle in paddles.iter() {
-            if collision_check(&transform, paddle) {
-                velocity.x = -velocity.x;
-                velocity.y = (transform.translation.y - paddle.translation.y) * 5.;
+        None => MatchRules::default(),
+    };
+    Ok((
+        controllers.first().copied().unwrap_or_default(),
+        controllers.get(1).copied().unwrap_or_default(),
+        rules,
+    ))
+}
+
+const USAGE: &str = "\
+usage: pong [options]
+
+  --player1 <controller>     who plays the left paddle: human, easy, normal or hard [default: human]
+  --player2 <controller>     who plays the right paddle [default: human]
+  --online <1|2>             play online as player 1 or 2, with --bind and --peer
+  --bind <address>           address to listen for the peer on, e.g. 127.0.0.1:7000
+  --peer <address>           address of the other player
+  --points <n>               points needed to win a set [default: 11]
+  --no-win-by-two            let a one-point lead win a set
+  --best-of <n>              sets in the match, an odd number [default: 1]";
+
+fn parse(mut args: Args) -> Result<(CommonArgs, PongPlugin), String> {
+    let common = CommonArgs::parse(&mut args, "pong")?;
+    let player1 = args.value::<Controller>("--player1")?;
+    let player2 = args.value::<Controller>("--player2")?;
+    let seat = args.value::<Seat>("--online")?;
+    let bind = args.value::<SocketAddr>("--bind")?;
+    let peer = args.value::<SocketAddr>("--peer")?;
+    let points = args.value::<usize>("--points")?;
+    let no_win_by_two = args.flag("--no-win-by-two");
+    let best_of = args.value::<usize>("--best-of")?;
+    args.finish()?;
+
+    let online = match (seat, bind, peer) {
+        (Some(seat), Some(local), Some(peer)) => Some(NetConfig { seat, local, peer }),
+        (None, None, None) => None,
+        (Some(_), _, _) => return Err("--online needs both --bind and --peer".to_string()),
+        _ => return Err("--bind and --peer are only used with --online".to_string()),
+    };
+
+    let (player1, player2, rules) = match &common.replay {
+        Some(replay) => {
+            if player1.is_some() || player2.is_some() || online.is_some() {
+                return Err(
+                    "--player1, --player2 and --online can't be used with --replay, \
+                            which plays back the players it was recorded with"
+                        .to_string(),
+                );
             }
-        } 
-
-        // check for collision with ceiling/floor
-        if transform.translation.y + BALL_RADIUS >= arena.height / 2.
-            || transform.translation.y - BALL_RADIUS <= -arena.height / 2.
-        {
-            velocity.y = -velocity.y;
-        }
-
-        transform.translation.x += velocity.x * time.delta_seconds();
-        transform.translation.y += velocity.y * time.delta_seconds();
-    }
-}
-
-fn collision_check(ball: &Transform, paddle: &Transform) -> bool {
-    let ball_x = ball.translation.x;
-    let ball_y = ball.translation.y;
-    let ball_width = BALL_RADIUS * 2.;
-    let ball_height = BALL_RADIUS * 2.;
-    let paddle_x = paddle.translation.x;
-    let paddle_y = paddle.translation.y;
-
-    if ball_x + ball_width / 2. >= paddle_x - PADDLE_WIDTH / 2.
-        && ball_x - ball_width / 2. <= paddle_x + PADDLE_WIDTH / 2.
-        && ball_y + ball_height / 2. >= paddle_y - PADDLE_HEIGHT / 2.
-        && ball_y - ball_height / 2. <= paddle_y + PADDLE_HEIGHT / 2.
-    {
-        return true;
-    } 
-    false
-}
-
-fn move_paddle_system(
-    time: Res<Time>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut set: ParamSet<(
-        Query<&mut Transform, (With<Paddle>, With<Player1>)>,
-        Query<&mut Transform, (With<Paddle>, With<Player2>)>,
-    )>,
-    arena: Res<Arena>,
-) {
-    for mut transform in set.p0().iter_mut() {
-        if keyboard_input.pressed(KeyCode::KeyW) {
-            transform.translation.y += 200. * time.delta_seconds();
-        }
-        if keyboard_input.pressed(KeyCode::KeyS) {
-            transform.translation.y -= 200. * time.delta_seconds();
-        }
-        if transform.translation.y + PADDLE_HEIGHT / 2. >= arena.height / 2. {
-            transform.translation.y = arena.height / 2. - PADDLE_HEIGHT / 2.;
+            if points.is_some() || no_win_by_two || best_of.is_some() {
+                return Err(
+                    "--points, --no-win-by-two and --best-of can't be used with --replay, \
+                     which plays back the rules it was recorded with"
+                        .to_string(),
+                );
+            }
+            parse_setup(&replay.setup)?
         }
-        if transform.translation.y - PADDLE_HEIGHT / 2. <= -arena.height / 2. {
-            transform.translation.y = -arena.height / 2. + PADDLE_HEIGHT / 2.;
+        None => {
+            let default = MatchRules::default();
+            let rules = MatchRules {
+                points_to_win: points.unwrap_or(default.points_to_win),
+                win_by_two: !no_win_by_two,
+                best_of: best_of.unwrap_or(default.best_of),
+                ..default
+            };
+            rules.validate()?;
+            (
+                player1.unwrap_or_default(),
+                player2.unwrap_or_default(),
+                rules,
+            )
         }
+    };
+    if online.is_some() && (player1 != Controller::Human || player2 != Controller::Human) {
+        return Err("--player1 and --player2 can't be used with --online, \
+                    which is always between two people"
+            .to_string());
     }
-
-    for mut transform in set.p1().iter_mut() {
-        if keyboard_input.pressed(KeyCode::ArrowUp) {
-            transform.translation.y += 200. * time.delta_seconds();
-        }
-        if keyboard_input.pressed(KeyCode::ArrowDown) {
-            transform.translation.y -= 200. * time.delta_seconds();
+    if common.headless && common.replay.is_none() {
+        if online.is_some() {
+            return Err("--online can't be used with --headless".to_string());
         }
-        if transform.translation.y + PADDLE_HEIGHT / 2. >= arena.height / 2. {
-            transform.translation.y = arena.height / 2. - PADDLE_HEIGHT / 2.;
-        }
-        if transform.translation.y - PADDLE_HEIGHT / 2. <= -arena.height / 2. {
-            transform.translation.y = -arena.height / 2. + PADDLE_HEIGHT / 2.;
-        }
-    }
-}
-
-fn setup_fps_counter(mut commands: Commands) {
-    // create our UI root node
-    // this is the wrapper/container for the text
-    let root = commands
-        .spawn((
-            FpsRoot,
-            NodeBundle {
-                // give it a dark background for readability
-                background_color: BackgroundColor(Color::BLACK.with_a(0.5)),
-                // make it "always on top" by setting the Z index to maximum
-                // we want it to be displayed over all other UI
-                z_index: ZIndex::Global(i32::MAX),
-                style: Style {
-                    position_type: PositionType::Absolute,
-                    // position it at the top-right corner
-                    // 1% away from the top window edge
-                    right: Val::Percent(1.),
-                    top: Val::Percent(1.),
-                    // set bottom/left to Auto, so it can be
-                    // automatically sized depending on the text
-                    bottom: Val::Auto,
-                    left: Val::Auto,
-                    // give it some padding for readability
-                    padding: UiRect::all(Val::Px(4.0)),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-        ))
-        .id();
-    // create our text
-    let text_fps = commands
-        .spawn((
-            FpsText,
-            TextBundle {
-                // use two sections, so it is easy to update just the number
-                text: Text::from_sections([
-                    TextSection {
-                        value: "FPS: ".into(),
-                        style: TextStyle {
-                            font_size: 16.0,
-                            color: Color::WHITE,
-                            // if you want to use your game's font asset,
-                            // uncomment this and provide the handle:
-                            // font: my_font_handle
-                            ..default()
-                        },
-                    },
-                    TextSection {
-                        value: " N/A".into(),
-                        style: TextStyle {
-                            font_size: 16.0,
-                            color: Color::WHITE,
-                            // if you want to use your game's font asset,
-                            // uncomment this and provide the handle:
-                            // font: my_font_handle
-                            ..default()
-                        },
-                    },
-                ]),
-                ..Default::default()
-            },
-        ))
-        .id();
-    commands.entity(root).push_children(&[text_fps]);
-}
-
-fn fps_text_update_system(
-    diagnostics: Res<DiagnosticsStore>,
-    mut query: Query<&mut Text, With<FpsText>>,
-) {
-    for mut text in &mut query {
-        // try to get a "smoothed" FPS value from Bevy
-        if let Some(value) = diagnostics
-            .get(&FrameTimeDiagnosticsPlugin::FPS)
-            .and_then(|fps| fps.smoothed())
-        {
-            // Format the number as to leave space for 4 digits, just in case,
-            // right-aligned and rounded. This helps readability when the
-            // number changes rapidly.
-            text.sections[1].value = format!("{value:>4.0}");
-
-            // Let's make it extra fancy by changing the color of the
-            // text according to the FPS value:
-            text.sections[1].style.color = if value >= 120.0 {
-                // Above 120 FPS, use green color
-                Color::rgb(0.0, 1.0, 0.0)
-            } else if value >= 60.0 {
-                // Between 60-120 FPS, gradually transition from yellow to green
-                Color::rgb((1.0 - (value - 60.0) / (120.0 - 60.0)) as f32, 1.0, 0.0)
-            } else if value >= 30.0 {
-                // Between 30-60 FPS, gradually transition from red to yellow
-                Color::rgb(1.0, ((value - 30.0) / (60.0 - 30.0)) as f32, 0.0)
-            } else {
-                // Below 30 FPS, use red color
-                Color::rgb(1.0, 0.0, 0.0)
-            }
-        } else {
-            // display "N/A" if we can't get a FPS measurement
-            // add an extra space to preserve alignment
-            text.sections[1].value = " N/A".into();
-            text.sections[1].style.color = Color::WHITE;
+        let humans = [("--player1", player1), ("--player2", player2)];
+        if let Some((flag, _)) = humans.iter().find(|(_, c)| *c == Controller::Human) {
+            return Err(format!(
+                "nobody can play without a window, so give {flag} a computer difficulty to run with --headless"
+            ));
         }
     }
-}
 
-fn score_text_update_system(
-    mut queries: ParamSet<(
-        Query<&mut Text, (With<Player1>, With<ScoreText>)>,
-        Query<&mut Text, (With<Player2>, With<ScoreText>)>,
-    )>,
-    score: Res<Score>,
-) {
-    for mut text in queries.p0().iter_mut() {
-        text.sections[0].value = score.player1.to_string();
-    }
-    for mut text in queries.p1().iter_mut() {
-        text.sections[0].value = score.player2.to_string();
-    }
+    let plugin = PongPlugin {
+        player1,
+        player2,
+        rules,
+        online,
+        replay: common.replay_mode(),
+        seed: common.seed,
+        tick_rate: common.tick_rate,
+        config_path: common.config.clone(),
+        controls_path: common.controls.clone(),
+        ..Default::default()
+    };
+    Ok((common, plugin))
 }
 
-fn fps_counter_showhide(
-    mut q: Query<&mut Visibility, With<FpsRoot>>,
-    kbd: Res<ButtonInput<KeyCode>>,
-) {
-    if kbd.just_pressed(KeyCode::F12) {
-        let mut vis = q.single_mut();
-        *vis = match *vis {
-            Visibility::Hidden => Visibility::Visible,
-            _ => Visibility::Hidden,
-        };
+fn main() {
+    let mut args = Args::from_env();
+    if args.flag("--help") {
+        println!("{USAGE}\n{COMMON_USAGE}");
+        return;
     }
-}
+    let (common, plugin) = match parse(args) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}\n{COMMON_USAGE}");
+            std::process::exit(2);
+        }
+    };
 
-impl Plugin for PongPlugin {
-    fn build(&self, app: &mut App) {
-        app.insert_resource(Arena {
-            width: 0.,
-            height: 0.,
-            wall_thickness: 4.,
-        });
-        app.insert_resource(Score {
-            player1: 0,
-            player2: 0,
-        });
-        app.add_systems(
-            Startup,
-            (
-                // setup_fps_counter,
-                setup_camera,
-                setup_ball,
-                (startup, setup_paddles, setup_arena, setup_score).chain(),
-            ),
-        );
-        app.add_systems(
-            Update,
-            (
-                // fps_text_update_system,
-                // fps_counter_showhide,
-                score_text_update_system,
-                ball_move_system,
-                move_paddle_system,
-            ),
-        );
-    }
+    let mut app = common.app("Pong");
+    app.add_plugins((plugin, FrameTimeDiagnosticsPlugin)).run();
 }
@@ -0,0 +1,722 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+use std::str::FromStr;
+
+use arcade_core::{
+    AgreedSeed, ArenaSetup, Ball, ConfigLock, GameRng, GameState, Hud, HudRegion, Paddle,
+    PaddleInput, PaddleInputs, PaddleMotion, Player, PlayerControls, Resimulating, Score, Tick,
+    Velocity,
+};
+use bevy::ecs::system::SystemState;
+use bevy::prelude::*;
+
+use crate::rules::{self, Sets};
+use crate::serve::{Rally, Serve, Server};
+
+/// Ticks the simulation may run past the last input heard from the peer
+/// before it stops and waits for more.
+const MAX_PREDICTION: u64 = 8;
+/// Ticks between the peers comparing checksums of the game state.
+const CHECKSUM_INTERVAL: u64 = 60;
+/// Checksums kept around for comparing against the peer's.
+const CHECKSUM_HISTORY: usize = 16;
+/// Most inputs sent in one packet. Anything older the peer hasn't
+/// acknowledged is given up on, which only happens if it's stopped listening.
+const MAX_INPUTS_PER_PACKET: usize = 128;
+const MAGIC: &[u8; 4] = b"PNG4";
+
+/// Which paddle this peer plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Seat {
+    Player1,
+    Player2,
+}
+
+/// An online match against one other peer, e.g.
+/// `pong --online 1 --bind 127.0.0.1:7000 --peer 127.0.0.1:7001` on one side
+/// and `pong --online 2 --bind 127.0.0.1:7001 --peer 127.0.0.1:7000` on the
+/// other.
+#[derive(Debug, Clone, Copy)]
+pub struct NetConfig {
+    pub seat: Seat,
+    /// Address to listen for the peer on.
+    pub local: SocketAddr,
+    pub peer: SocketAddr,
+}
+
+impl FromStr for Seat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Seat::Player1),
+            "2" => Ok(Seat::Player2),
+            _ => Err(format!("unknown player '{s}', expected 1 or 2")),
+        }
+    }
+}
+
+/// Everything a tick can change, saved at the start of each tick that may
+/// still have to be simulated again.
+struct Snapshot {
+    tick: u64,
+    balls: Vec<(Entity, Vec3, Velocity)>,
+    paddles: Vec<(Entity, Vec3)>,
+    score: Score,
+    sets: (usize, usize),
+    serve: Serve,
+    rally: Rally,
+    rng: GameRng,
+}
+
+impl Snapshot {
+    fn save(world: &mut World, tick: u64) -> Self {
+        let balls = world
+            .query_filtered::<(Entity, &Transform, &Velocity), With<Ball>>()
+            .iter(world)
+            .map(|(entity, transform, velocity)| (entity, transform.translation, *velocity))
+            .collect();
+        let paddles = world
+            .query_filtered::<(Entity, &Transform), With<Paddle>>()
+            .iter(world)
+            .map(|(entity, transform)| (entity, transform.translation))
+            .collect();
+        let sets = world.resource::<Sets>();
+        Snapshot {
+            tick,
+            balls,
+            paddles,
+            score: *world.resource::<Score>(),
+            sets: (sets.player1, sets.player2),
+            serve: *world.resource::<Serve>(),
+            rally: *world.resource::<Rally>(),
+            rng: world.resource::<GameRng>().clone(),
+        }
+    }
+
+    fn restore(&self, world: &mut World) {
+        for &(entity, translation, velocity) in &self.balls {
+            if let Some(mut ball) = world.get_entity_mut(entity) {
+                if let Some(mut transform) = ball.get_mut::<Transform>() {
+                    transform.translation = translation;
+                }
+                if let Some(mut current) = ball.get_mut::<Velocity>() {
+                    *current = velocity;
+                }
+            }
+        }
+        for &(entity, translation) in &self.paddles {
+            if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+                transform.translation = translation;
+            }
+            // the paddle was standing here at the start of the tick
+            if let Some(mut motion) = world.get_mut::<PaddleMotion>(entity) {
+                *motion = PaddleMotion::at(translation.truncate());
+            }
+        }
+        *world.resource_mut::<Score>() = self.score;
+        let mut sets = world.resource_mut::<Sets>();
+        (sets.player1, sets.player2) = self.sets;
+        *world.resource_mut::<Serve>() = self.serve;
+        *world.resource_mut::<Rally>() = self.rally;
+        *world.resource_mut::<GameRng>() = self.rng.clone();
+        world.resource_mut::<Tick>().0 = self.tick;
+    }
+
+    /// Hashes the state without entity ids, which the peers needn't share.
+    fn checksum(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (_, translation, velocity) in &self.balls {
+            for value in [
+                translation.x,
+                translation.y,
+                velocity.speed,
+                velocity.direction.x,
+                velocity.direction.y,
+            ] {
+                value.to_bits().hash(&mut hasher);
+            }
+        }
+        let mut paddles: Vec<[u32; 2]> = self
+            .paddles
+            .iter()
+            .map(|(_, translation)| [translation.x.to_bits(), translation.y.to_bits()])
+            .collect();
+        paddles.sort_by(|a, b| f32::from_bits(a[0]).total_cmp(&f32::from_bits(b[0])));
+        paddles.hash(&mut hasher);
+        (self.score.player1, self.score.player2, self.sets).hash(&mut hasher);
+        let serve = (self.serve.server, self.serve.remaining.map(f32::to_bits));
+        serve.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// What the peers send each other every frame. Inputs the peer hasn't
+/// acknowledged are sent again each time, so lost packets are made up for by
+/// the next one and late ones are simply ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packet {
+    /// Which match the inputs belong to, counting up from 1.
+    pub round: u32,
+    /// The sender's current tick.
+    pub tick: u64,
+    /// How many ticks the sender thinks it is ahead.
+    pub advantage: i64,
+    /// Number of the receiver's inputs the sender has, from the start.
+    pub ack: u64,
+    /// Tick of the first input in `inputs`.
+    pub start: u64,
+    pub inputs: Vec<PaddleInput>,
+    /// The sender's latest checksum and the tick it's for.
+    pub checksum: Option<(u64, u64)>,
+    /// The seed player 1 picked for the session, which player 2 plays with
+    /// too. Player 2 sends `None`.
+    pub seed: Option<u64>,
+}
+
+impl Packet {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64 + self.inputs.len() * 4);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.round.to_le_bytes());
+        bytes.extend_from_slice(&self.tick.to_le_bytes());
+        bytes.extend_from_slice(&self.advantage.to_le_bytes());
+        bytes.extend_from_slice(&self.ack.to_le_bytes());
+        match self.checksum {
+            Some((tick, value)) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&tick.to_le_bytes());
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+        match self.seed {
+            Some(seed) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&seed.to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&self.start.to_le_bytes());
+        bytes.extend(self.inputs.iter().flat_map(|input| input.to_bytes()));
+        bytes
+    }
+
+    /// Returns `None` for anything that isn't a whole packet.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return None;
+        }
+        let round = u32::from_le_bytes(reader.array()?);
+        let tick = u64::from_le_bytes(reader.array()?);
+        let advantage = i64::from_le_bytes(reader.array()?);
+        let ack = u64::from_le_bytes(reader.array()?);
+        let checksum = match reader.take(1)?[0] {
+            0 => None,
+            _ => Some((
+                u64::from_le_bytes(reader.array()?),
+                u64::from_le_bytes(reader.array()?),
+            )),
+        };
+        let seed = match reader.take(1)?[0] {
+            0 => None,
+            _ => Some(u64::from_le_bytes(reader.array()?)),
+        };
+        let start = u64::from_le_bytes(reader.array()?);
+        if reader.0.len() % 4 != 0 {
+            return None;
+        }
+        let inputs = reader
+            .0
+            .chunks_exact(4)
+            .map(|bytes| PaddleInput::from_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        Some(Packet {
+            round,
+            tick,
+            advantage,
+            ack,
+            start,
+            inputs: inputs.collect(),
+            checksum,
+            seed,
+        })
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+}
+
+/// A running online match.
+///
+/// Each peer simulates every tick straight away, guessing that the other
+/// player is still pressing whatever they last pressed. When the real input
+/// for a tick turns up and differs from the guess, the game is put back to
+/// how it was at that tick and simulated forward again with the right input.
+#[derive(Resource)]
+pub struct NetSession {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    seat: Seat,
+    /// What every match's seed is worked out from, once it's known. Player
+    /// 1 picks it and player 2 hears it from them.
+    seed: Option<u64>,
+    round: u32,
+    /// This peer's input for each tick simulated.
+    local_inputs: Vec<PaddleInput>,
+    /// The peer's inputs, filled in as they arrive in whatever order.
+    remote_inputs: Vec<Option<PaddleInput>>,
+    /// The peer's input each tick was simulated with, real or guessed.
+    used_inputs: Vec<PaddleInput>,
+    /// Number of ticks from the start the peer's inputs are all known for.
+    confirmed: u64,
+    /// Number of this peer's inputs the peer has, from the start.
+    acked: u64,
+    remote_tick: u64,
+    remote_advantage: i64,
+    /// Earliest tick simulated with a wrong guess.
+    rollback_from: Option<u64>,
+    snapshots: VecDeque<Snapshot>,
+    checksums: VecDeque<(u64, u64)>,
+    remote_checksums: VecDeque<(u64, u64)>,
+    /// First tick the peers were found to disagree on.
+    desync: Option<u64>,
+    /// Whether the simulation is held up waiting for the peer.
+    waiting: bool,
+}
+
+impl NetSession {
+    /// Listens for the peer. `seed` is only used by player 1, who picks
+    /// the seed for both of them.
+    pub fn bind(config: NetConfig, seed: u64) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(config.local)?;
+        socket.set_nonblocking(true)?;
+        Ok(NetSession {
+            socket,
+            peer: config.peer,
+            seat: config.seat,
+            seed: (config.seat == Seat::Player1).then_some(seed),
+            round: 0,
+            local_inputs: Vec::new(),
+            remote_inputs: Vec::new(),
+            used_inputs: Vec::new(),
+            confirmed: 0,
+            acked: 0,
+            remote_tick: 0,
+            remote_advantage: 0,
+            rollback_from: None,
+            snapshots: VecDeque::new(),
+            checksums: VecDeque::new(),
+            remote_checksums: VecDeque::new(),
+            desync: None,
+            waiting: false,
+        })
+    }
+
+    /// Forgets the last match and starts counting ticks for the next one.
+    fn next_round(&mut self) {
+        self.round += 1;
+        self.local_inputs.clear();
+        self.remote_inputs.clear();
+        self.used_inputs.clear();
+        self.confirmed = 0;
+        self.acked = 0;
+        self.remote_tick = 0;
+        self.remote_advantage = 0;
+        self.rollback_from = None;
+        self.snapshots.clear();
+        self.checksums.clear();
+        self.remote_checksums.clear();
+        self.desync = None;
+        self.waiting = false;
+    }
+
+    fn receive(&mut self) {
+        let mut buffer = [0; 1500];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((len, from)) if from == self.peer => {
+                    if let Some(packet) = Packet::decode(&buffer[..len]) {
+                        self.handle(packet);
+                    }
+                }
+                Ok(_) => {}
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                // some platforms report the peer not listening yet here
+                Err(err) if err.kind() == ErrorKind::ConnectionReset => {}
+                Err(err) => {
+                    warn!("netplay: {err}");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle(&mut self, packet: Packet) {
+        if self.seed.is_none() && self.seat == Seat::Player2 {
+            self.seed = packet.seed;
+        }
+        if packet.round != self.round {
+            return;
+        }
+        if packet.tick >= self.remote_tick {
+            self.remote_tick = packet.tick;
+            self.remote_advantage = packet.advantage;
+        }
+        self.acked = self.acked.max(packet.ack);
+
+        // the peer stops for this one within `MAX_PREDICTION` ticks, give or
+        // take a frame's worth, so inputs much further ahead are stray or
+        // hostile and mustn't be made room for. Real ones are sent again.
+        let window = self.local_inputs.len() + MAX_PREDICTION as usize + MAX_INPUTS_PER_PACKET;
+        match packet.start.checked_add(packet.inputs.len() as u64) {
+            Some(end) if end <= window as u64 => {}
+            _ => return,
+        }
+        for (tick, input) in (packet.start..).zip(packet.inputs) {
+            let slot = tick as usize;
+            if self.remote_inputs.len() <= slot {
+                self.remote_inputs.resize(slot + 1, None);
+            }
+            if self.remote_inputs[slot].is_some() {
+                continue;
+            }
+            self.remote_inputs[slot] = Some(input);
+            if self
+                .used_inputs
+                .get(slot)
+                .is_some_and(|&used| used != input)
+            {
+                self.rollback_from = Some(self.rollback_from.map_or(tick, |from| from.min(tick)));
+            }
+        }
+        while matches!(
+            self.remote_inputs.get(self.confirmed as usize),
+            Some(Some(_))
+        ) {
+            self.confirmed += 1;
+        }
+
+        if let Some(checksum) = packet.checksum {
+            if !self.remote_checksums.contains(&checksum) {
+                self.remote_checksums.push_back(checksum);
+                if self.remote_checksums.len() > CHECKSUM_HISTORY {
+                    self.remote_checksums.pop_front();
+                }
+                self.check_desync();
+            }
+        }
+    }
+
+    /// First tick the peers were found to disagree on, if they have.
+    pub fn desync(&self) -> Option<u64> {
+        self.desync
+    }
+
+    /// Latest tick checksummed here for the peer to check against its own.
+    pub fn in_sync_to(&self) -> u64 {
+        self.checksums.back().map_or(0, |&(tick, _)| tick)
+    }
+
+    fn send(&self, tick: u64) {
+        let start = (self.acked as usize)
+            .max(
+                self.local_inputs
+                    .len()
+                    .saturating_sub(MAX_INPUTS_PER_PACKET),
+            )
+            .min(self.local_inputs.len());
+        let packet = Packet {
+            round: self.round,
+            tick,
+            advantage: tick as i64 - self.remote_tick as i64,
+            ack: self.confirmed,
+            start: start as u64,
+            inputs: self.local_inputs[start..].to_vec(),
+            checksum: self.checksums.back().copied(),
+            seed: self.seed.filter(|_| self.seat == Seat::Player1),
+        };
+        // nothing to do if this fails, the same inputs go out again next frame
+        let _ = self.socket.send_to(&packet.encode(), self.peer);
+    }
+
+    /// The peer's input for `tick` if it has arrived, or a guess that it
+    /// hasn't changed since the last one that did.
+    fn remote_input(&self, tick: u64) -> PaddleInput {
+        if let Some(Some(input)) = self.remote_inputs.get(tick as usize) {
+            return *input;
+        }
+        match self.confirmed.checked_sub(1) {
+            Some(last) => self.remote_inputs[last as usize].unwrap_or_default(),
+            None => PaddleInput::default(),
+        }
+    }
+
+    /// Sets the inputs for simulating `tick` and saves the state it starts
+    /// from. This peer's input is read from the controls the first time the
+    /// tick is simulated and reused if it's simulated again.
+    fn prepare_tick(&mut self, world: &mut World, tick: u64) {
+        let slot = tick as usize;
+        if slot >= self.local_inputs.len() {
+            let mut controls = SystemState::<PlayerControls>::new(world);
+            let controls = controls.get(world);
+            // one person plays from here, with either player's controls and
+            // touching anywhere
+            let [player1, player2] = [Player::One, Player::Two]
+                .map(|player| controls.paddle_input(player, |point| Some(point.y)));
+            let movement = if player1.movement.unsigned_abs() >= player2.movement.unsigned_abs() {
+                player1.movement
+            } else {
+                player2.movement
+            };
+            self.local_inputs.push(PaddleInput {
+                movement,
+                serve: player1.serve || player2.serve,
+                target: player1.target.or(player2.target),
+            });
+        }
+        let local = self.local_inputs[slot];
+        let remote = self.remote_input(tick);
+        self.used_inputs.truncate(slot);
+        self.used_inputs.push(remote);
+
+        *world.resource_mut::<PaddleInputs>() = match self.seat {
+            Seat::Player1 => PaddleInputs {
+                player1: local,
+                player2: remote,
+            },
+            Seat::Player2 => PaddleInputs {
+                player1: remote,
+                player2: local,
+            },
+        };
+        while self.snapshots.back().is_some_and(|s| s.tick >= tick) {
+            self.snapshots.pop_back();
+        }
+        self.snapshots.push_back(Snapshot::save(world, tick));
+    }
+
+    /// Checksums the snapshots that can no longer be rolled back and drops
+    /// the ones that won't be needed again.
+    fn settle(&mut self, tick: u64) {
+        let settled = self.confirmed.min(tick);
+        let last_checked = self.checksums.back().map_or(0, |&(tick, _)| tick);
+        let new: Vec<(u64, u64)> = self
+            .snapshots
+            .iter()
+            .filter(|s| s.tick <= settled && s.tick > last_checked)
+            .filter(|s| s.tick % CHECKSUM_INTERVAL == 0)
+            .map(|s| (s.tick, s.checksum()))
+            .collect();
+        if !new.is_empty() {
+            self.checksums.extend(new);
+            while self.checksums.len() > CHECKSUM_HISTORY {
+                self.checksums.pop_front();
+            }
+            self.check_desync();
+        }
+        while self.snapshots.front().is_some_and(|s| s.tick < settled) {
+            self.snapshots.pop_front();
+        }
+    }
+
+    fn check_desync(&mut self) {
+        if self.desync.is_some() {
+            return;
+        }
+        for &(tick, value) in &self.remote_checksums {
+            if let Some(&(_, local)) = self.checksums.iter().find(|&&(t, _)| t == tick) {
+                if local != value {
+                    error!("netplay: desync at tick {tick}");
+                    self.desync = Some(tick);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Trades inputs with the peer, and holds the simulation back when it gets
+/// too far ahead of the peer.
+fn net_sync_system(
+    mut session: ResMut<NetSession>,
+    tick: Res<Tick>,
+    state: Res<State<GameState>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    session.receive();
+    session.send(tick.0);
+
+    let playing = *state.get() == GameState::Playing;
+    session.waiting = playing && tick.0 >= session.confirmed + MAX_PREDICTION;
+    if session.waiting {
+        time.pause();
+    } else {
+        time.unpause();
+    }
+    // both peers see the other as behind by the trip time, so only the
+    // difference between the two views says who is really ahead
+    let advantage = tick.0 as i64 - session.remote_tick as i64 - session.remote_advantage;
+    let speed = if playing && advantage > 2 { 0.9 } else { 1. };
+    if time.relative_speed() != speed {
+        time.set_relative_speed(speed);
+    }
+}
+
+/// Keeps the seed for the next match ready for `reseed_for_match`, or holds
+/// the menus until it's known so the peers start every match the same.
+fn agree_seed_system(
+    mut commands: Commands,
+    session: Res<NetSession>,
+    agreed: Option<Res<AgreedSeed>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    match session.seed {
+        Some(seed) => {
+            let next = AgreedSeed(seed.wrapping_add(session.round.into()));
+            if agreed.as_deref() != Some(&next) {
+                commands.insert_resource(next);
+            }
+        }
+        None => {
+            let leaving_menus = matches!(state.get(), GameState::MainMenu | GameState::GameOver)
+                && next_state.0 == Some(GameState::Countdown);
+            if leaving_menus {
+                next_state.0 = None;
+            }
+        }
+    }
+}
+
+/// Runs before each simulated tick. Re-simulates from the earliest tick
+/// that was guessed wrong, then sets up the inputs for the coming tick.
+///
+/// Only the ball, paddles, score and random numbers are rolled back. A
+/// wrong guess that ends the match can't be taken back, but that needs the
+/// guess to decide the winning point.
+fn rollback_system(world: &mut World) {
+    world.resource_scope(|world, mut session: Mut<NetSession>| {
+        let tick = world.resource::<Tick>().0;
+        if let Some(from) = session.rollback_from.take() {
+            match session.snapshots.iter().position(|s| s.tick == from) {
+                Some(index) => {
+                    session.snapshots[index].restore(world);
+                    world.insert_resource(Resimulating);
+                    for replayed in from..tick {
+                        session.prepare_tick(world, replayed);
+                        world.run_schedule(FixedUpdate);
+                    }
+                    world.remove_resource::<Resimulating>();
+                }
+                None => warn!("netplay: can't roll back to tick {from}, it's too long ago"),
+            }
+        }
+        session.settle(tick);
+        session.prepare_tick(world, tick);
+    });
+}
+
+/// Resets the session for a new match and gives the first serve to the same
+/// player on both peers, alternating between matches.
+fn start_round(mut session: ResMut<NetSession>, mut serve: ResMut<Serve>) {
+    session.next_round();
+    let server = if session.round % 2 == 1 {
+        Server::Player1
+    } else {
+        Server::Player2
+    };
+    *serve = Serve::immediately(server);
+}
+
+#[derive(Component)]
+struct NetStatusText;
+
+/// Shows how the connection is doing below the right of the arena.
+fn setup_status_text(mut commands: Commands, hud: Res<Hud>) {
+    commands
+        .entity(hud.region(HudRegion::BottomRight))
+        .with_children(|region| {
+            region.spawn((
+                NetStatusText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..Default::default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn status_text_update_system(
+    session: Res<NetSession>,
+    mut query: Query<&mut Text, With<NetStatusText>>,
+) {
+    let (value, color) = match session.desync() {
+        Some(tick) => (format!("Desync at tick {tick}"), Color::RED),
+        None if session.waiting || session.seed.is_none() => {
+            ("Waiting for opponent...".to_string(), Color::WHITE)
+        }
+        None => (
+            format!("Online, in sync to tick {}", session.in_sync_to()),
+            Color::GRAY,
+        ),
+    };
+    for mut text in query.iter_mut() {
+        text.sections[0].value.clone_from(&value);
+        text.sections[0].style.color = color;
+    }
+}
+
+/// Plays pong against a peer over UDP with rollback.
+pub struct NetPlugin {
+    pub config: NetConfig,
+}
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        // player 1 plays with the seed they'd have used offline
+        let seed = app.world.resource::<GameRng>().seed();
+        let session = NetSession::bind(self.config, seed)
+            .unwrap_or_else(|err| panic!("netplay: can't listen on {}: {err}", self.config.local));
+        app.insert_resource(session);
+        // the peer plays with its own config, and never hears of changes here
+        app.insert_resource(ConfigLock::Session);
+        app.add_systems(Startup, setup_status_text.after(ArenaSetup));
+        app.add_systems(PreUpdate, (net_sync_system, agree_seed_system).chain());
+        app.add_systems(Update, status_text_update_system);
+        app.add_systems(
+            FixedPreUpdate,
+            rollback_system.run_if(in_state(GameState::Playing)),
+        );
+        for from in [GameState::MainMenu, GameState::GameOver] {
+            app.add_systems(
+                OnTransition {
+                    from,
+                    to: GameState::Countdown,
+                },
+                start_round.after(rules::reset_match),
+            );
+        }
+    }
+}
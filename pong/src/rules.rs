@@ -0,0 +1,238 @@
+use arcade_core::{
+    spawn_menu, Ball, GameEvents, GameRng, GameState, Hud, HudRegion, MatchEnded, MenuAction,
+    Paddle, Player, Player1, Player2, Score, Velocity,
+};
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ai::AiPaddle;
+use crate::serve::{Serve, ServeRule, Server};
+
+/// When a game and the match are won.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct MatchRules {
+    /// Points needed to win a set.
+    pub points_to_win: usize,
+    /// Whether a set has to be won by a two-point lead, playing on past
+    /// `points_to_win` until someone has it.
+    pub win_by_two: bool,
+    /// Number of sets in the match. The first player to win more than half of
+    /// them wins the match.
+    pub best_of: usize,
+    pub serve: ServeRule,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        MatchRules {
+            points_to_win: 11,
+            win_by_two: true,
+            best_of: 1,
+            serve: ServeRule::default(),
+        }
+    }
+}
+
+impl MatchRules {
+    /// Fails on rules that couldn't decide a match.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.points_to_win == 0 {
+            return Err("a set needs at least 1 point to win".to_string());
+        }
+        if self.best_of.is_multiple_of(2) {
+            return Err(format!(
+                "a match can't be best of {}, it needs an odd number of sets so one player wins more",
+                self.best_of
+            ));
+        }
+        Ok(())
+    }
+
+    /// The rules as a line of a replay's setup, e.g. `rules 11 2 3` for first
+    /// to 11, won by two points, best of 3.
+    pub fn to_setup(&self) -> String {
+        let lead = if self.win_by_two { 2 } else { 1 };
+        format!("rules {} {lead} {}", self.points_to_win, self.best_of)
+    }
+
+    /// Reads back a line written by `to_setup`. The serve rule isn't kept, so
+    /// it's the default one.
+    pub fn from_setup(line: &str) -> Option<Self> {
+        let mut values = line.strip_prefix("rules ")?.split(' ').map(str::parse);
+        let rules = MatchRules {
+            points_to_win: values.next()?.ok()?,
+            win_by_two: match values.next()?.ok()? {
+                1 => false,
+                2 => true,
+                _ => return None,
+            },
+            best_of: values.next()?.ok()?,
+            serve: ServeRule::default(),
+        };
+        let valid = values.next().is_none() && rules.validate().is_ok();
+        valid.then_some(rules)
+    }
+
+    fn set_winner(&self, score: &Score) -> Option<Winner> {
+        let lead = if self.win_by_two { 2 } else { 1 };
+        if score.player1 >= self.points_to_win && score.player1 >= score.player2 + lead {
+            Some(Winner::Player1)
+        } else if score.player2 >= self.points_to_win && score.player2 >= score.player1 + lead {
+            Some(Winner::Player2)
+        } else {
+            None
+        }
+    }
+
+    fn sets_to_win(&self) -> usize {
+        self.best_of / 2 + 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winner {
+    Player1,
+    Player2,
+}
+
+impl Winner {
+    pub fn player(self) -> Player {
+        match self {
+            Winner::Player1 => Player::One,
+            Winner::Player2 => Player::Two,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Sets {
+    pub player1: usize,
+    pub player2: usize,
+    pub winner: Option<Winner>,
+}
+
+#[derive(Component)]
+pub struct SetsText;
+
+/// Puts each player's sets won next to their score.
+pub fn setup_sets_text(mut commands: Commands, hud: Res<Hud>, rules: Res<MatchRules>) {
+    if rules.best_of <= 1 {
+        return;
+    }
+    let style = TextStyle {
+        font_size: 18.0,
+        color: Color::GRAY,
+        ..Default::default()
+    };
+    commands
+        .entity(hud.region(HudRegion::TopLeft))
+        .with_children(|region| {
+            region.spawn((
+                Player1,
+                SetsText,
+                TextBundle::from_section("Sets: 0", style.clone()),
+            ));
+        });
+    commands
+        .entity(hud.region(HudRegion::TopRight))
+        .with_children(|region| {
+            region.spawn((
+                Player2,
+                SetsText,
+                TextBundle::from_section("Sets: 0", style),
+            ));
+        });
+}
+
+/// Awards a set once a player meets the match rules, and ends the match once
+/// a player has won enough sets.
+pub fn match_rules_system(
+    rules: Res<MatchRules>,
+    mut score: ResMut<Score>,
+    mut sets: ResMut<Sets>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut ended: GameEvents<MatchEnded>,
+) {
+    let Some(winner) = rules.set_winner(&score) else {
+        return;
+    };
+    match winner {
+        Winner::Player1 => sets.player1 += 1,
+        Winner::Player2 => sets.player2 += 1,
+    }
+
+    if sets.player1.max(sets.player2) >= rules.sets_to_win() {
+        // leave the final score up behind the victory screen
+        sets.winner = Some(winner);
+        next_state.set(GameState::GameOver);
+        ended.send(MatchEnded {
+            winner: Some(winner.player()),
+        });
+    } else {
+        score.player1 = 0;
+        score.player2 = 0;
+    }
+}
+
+pub fn sets_text_update_system(
+    mut queries: ParamSet<(
+        Query<&mut Text, (With<Player1>, With<SetsText>)>,
+        Query<&mut Text, (With<Player2>, With<SetsText>)>,
+    )>,
+    sets: Res<Sets>,
+) {
+    for mut text in queries.p0().iter_mut() {
+        text.sections[0].value = format!("Sets: {}", sets.player1);
+    }
+    for mut text in queries.p1().iter_mut() {
+        text.sections[0].value = format!("Sets: {}", sets.player2);
+    }
+}
+
+pub fn setup_victory_screen(mut commands: Commands, score: Res<Score>, sets: Res<Sets>) {
+    let winner = match sets.winner {
+        Some(Winner::Player1) => "Player 1",
+        Some(Winner::Player2) => "Player 2",
+        None => return,
+    };
+    spawn_menu(
+        &mut commands,
+        &format!("{winner} wins!"),
+        Some(format!("{} - {}", score.player1, score.player2)),
+        &[
+            ("Rematch", MenuAction::Play),
+            ("Main menu", MenuAction::MainMenu),
+            ("Quit", MenuAction::Quit),
+        ],
+    );
+}
+
+/// Clears the score, centres the paddles and picks who serves first when a
+/// new match starts, so every match starts the same way apart from the serve.
+pub fn reset_match(
+    mut score: ResMut<Score>,
+    mut sets: ResMut<Sets>,
+    mut serve: ResMut<Serve>,
+    mut balls: Query<(&mut Transform, &mut Velocity), With<Ball>>,
+    mut paddles: Query<(&mut Transform, Option<&mut AiPaddle>), (With<Paddle>, Without<Ball>)>,
+    mut rng: ResMut<GameRng>,
+) {
+    *score = Score::default();
+    *sets = Sets::default();
+    for (mut transform, ai) in paddles.iter_mut() {
+        transform.translation.y = 0.;
+        if let Some(mut ai) = ai {
+            ai.reset();
+        }
+    }
+    for (mut transform, mut velocity) in balls.iter_mut() {
+        transform.translation = Vec3::new(0., 0., 0.);
+        *velocity = Velocity::default();
+    }
+    let server = if rng.gen() {
+        Server::Player1
+    } else {
+        Server::Player2
+    };
+    *serve = Serve::immediately(server);
+}
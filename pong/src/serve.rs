@@ -0,0 +1,226 @@
+use arcade_core::{
+    Ball, BallCollision, GameConfig, GameEvents, GameRng, Hud, HudRegion, Paddle, PaddleInputs,
+    Player, Player1, ServeStarted, Velocity,
+};
+use bevy::prelude::*;
+use rand::Rng;
+
+/// Seconds the ball waits in front of the server after a point.
+pub const SERVE_DELAY: f32 = 2.;
+/// Gap between the server's paddle and the waiting ball.
+const SERVE_GAP: f32 = 4.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Server {
+    Player1,
+    Player2,
+}
+
+impl Server {
+    fn other(self) -> Self {
+        match self {
+            Server::Player1 => Server::Player2,
+            Server::Player2 => Server::Player1,
+        }
+    }
+
+    pub fn player(self) -> Player {
+        match self {
+            Server::Player1 => Player::One,
+            Server::Player2 => Player::Two,
+        }
+    }
+
+    /// Which way along x the server hits the ball.
+    fn direction(self) -> f32 {
+        match self {
+            Server::Player1 => 1.,
+            Server::Player2 => -1.,
+        }
+    }
+}
+
+/// Who serves after a point.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ServeRule {
+    /// The player who lost the point.
+    #[default]
+    Loser,
+    /// Each player in turn.
+    Alternate,
+}
+
+/// The ball held in front of a player's paddle, waiting to be served.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Serve {
+    pub server: Server,
+    /// Seconds left before the ball is launched, or `None` once it's in play.
+    pub remaining: Option<f32>,
+}
+
+impl Serve {
+    /// A serve that launches on the first tick of play, for the start of a
+    /// match.
+    pub fn immediately(server: Server) -> Self {
+        Serve {
+            server,
+            remaining: Some(0.),
+        }
+    }
+
+    /// Hands the serve on after a point won by `winner`.
+    pub fn after_point(&mut self, rule: ServeRule, winner: Server) {
+        self.server = match rule {
+            ServeRule::Loser => winner.other(),
+            ServeRule::Alternate => self.server.other(),
+        };
+        self.remaining = Some(SERVE_DELAY);
+    }
+}
+
+impl Default for Serve {
+    fn default() -> Self {
+        Serve::immediately(Server::Player1)
+    }
+}
+
+/// Times the ball has been hit with a paddle since the last serve.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rally(pub u32);
+
+/// Holds the ball in front of the server's paddle until the delay runs out
+/// or they press serve, then launches it at the base speed and a random
+/// angle towards the other player.
+pub fn serve_system(
+    time: Res<Time>,
+    inputs: Res<PaddleInputs>,
+    config: Res<GameConfig>,
+    mut serve: ResMut<Serve>,
+    mut rally: ResMut<Rally>,
+    mut rng: ResMut<GameRng>,
+    mut balls: Query<(Entity, &mut Transform, &mut Velocity), With<Ball>>,
+    paddles: Query<(&Transform, Has<Player1>), (With<Paddle>, Without<Ball>)>,
+    mut serves: GameEvents<ServeStarted>,
+) {
+    let Some(remaining) = serve.remaining else {
+        return;
+    };
+    let server = serve.server;
+    let paddle = paddles
+        .iter()
+        .find(|(_, is_player1)| *is_player1 == (server == Server::Player1))
+        .map(|(transform, _)| transform.translation);
+    let pressed = match server {
+        Server::Player1 => inputs.player1.serve,
+        Server::Player2 => inputs.player2.serve,
+    };
+    let remaining = remaining - time.delta_seconds();
+    let launch = remaining <= 0. || pressed;
+
+    for (ball, mut transform, mut velocity) in balls.iter_mut() {
+        if let Some(paddle) = paddle {
+            let offset = config.paddle_width / 2. + config.ball_radius + SERVE_GAP;
+            transform.translation.x = paddle.x + server.direction() * offset;
+            transform.translation.y = paddle.y;
+        }
+        *velocity = if launch {
+            let max_angle = config.max_serve_angle.to_radians();
+            let angle = rng.gen_range(-max_angle..=max_angle);
+            let direction = Vec2::new(server.direction() * angle.cos(), angle.sin());
+            serves.send(ServeStarted {
+                ball,
+                server: server.player(),
+                point: transform.translation.truncate(),
+                speed: config.ball_speed,
+            });
+            Velocity::new(config.ball_speed, direction)
+        } else {
+            Velocity::default()
+        };
+    }
+    serve.remaining = (!launch).then_some(remaining);
+    if launch {
+        rally.0 = 0;
+    }
+}
+
+#[derive(Component)]
+pub struct ServeText;
+
+/// Counts down to the serve over the middle of the arena.
+pub fn setup_serve_text(mut commands: Commands, hud: Res<Hud>) {
+    commands
+        .entity(hud.region(HudRegion::Centre))
+        .with_children(|region| {
+            region.spawn((
+                ServeText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 60.0,
+                        color: Color::GRAY,
+                        ..Default::default()
+                    },
+                ),
+            ));
+        });
+}
+
+pub fn serve_text_update_system(serve: Res<Serve>, mut query: Query<&mut Text, With<ServeText>>) {
+    let value = match serve.remaining {
+        Some(remaining) if remaining > 0. => remaining.ceil().to_string(),
+        _ => String::new(),
+    };
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+/// Counts the paddle hits made during the physics step.
+pub fn count_rally_system(
+    mut collisions: EventReader<BallCollision>,
+    paddles: Query<(), With<Paddle>>,
+    mut rally: ResMut<Rally>,
+) {
+    for collision in collisions.read() {
+        if paddles.contains(collision.other) {
+            rally.0 += 1;
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct RallyText;
+
+/// Shows the rally under the middle of the arena once it gets going.
+pub fn setup_rally_text(mut commands: Commands, hud: Res<Hud>) {
+    commands
+        .entity(hud.region(HudRegion::Bottom))
+        .with_children(|region| {
+            region.spawn((
+                RallyText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::GRAY,
+                        ..Default::default()
+                    },
+                ),
+            ));
+        });
+}
+
+pub fn rally_text_update_system(rally: Res<Rally>, mut query: Query<&mut Text, With<RallyText>>) {
+    let value = match rally.0 {
+        0 => String::new(),
+        hits => format!("Rally: {hits}"),
+    };
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
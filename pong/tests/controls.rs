@@ -0,0 +1,57 @@
+use arcade_core::testing::TestApp;
+use arcade_core::{Action, Binding, GameState, InputMap, Player};
+use bevy::input::keyboard::{Key, KeyboardInput, NativeKey};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use pong::PongPlugin;
+
+/// The controls menu, with player 1's "move up" row selected.
+fn controls_menu() -> TestApp {
+    let mut app = TestApp::new(PongPlugin::default());
+    // the first update heads for a match, as it does when running headless
+    app.set_state(GameState::MainMenu);
+    app.set_state(GameState::Controls);
+    app
+}
+
+/// Presses `key` and lets go of it again, a frame each.
+fn tap(app: &mut TestApp, key: KeyCode) {
+    for state in [ButtonState::Pressed, ButtonState::Released] {
+        app.world_mut().send_event(KeyboardInput {
+            key_code: key,
+            logical_key: Key::Unidentified(NativeKey::Unidentified),
+            state,
+            window: Entity::PLACEHOLDER,
+        });
+        app.run_ticks(1);
+    }
+}
+
+fn move_up(app: &TestApp) -> Vec<Binding> {
+    app.world()
+        .resource::<InputMap>()
+        .get(Player::One)
+        .get(Action::MoveUp)
+        .to_vec()
+}
+
+#[test]
+fn the_key_being_bound_is_not_also_taken_by_the_menu() {
+    let mut app = controls_menu();
+
+    // S would move the selection down to "move down" if the menu saw it
+    tap(&mut app, KeyCode::Enter);
+    tap(&mut app, KeyCode::KeyS);
+    assert!(move_up(&app).contains(&Binding::Key(KeyCode::KeyS)));
+    tap(&mut app, KeyCode::Enter);
+    tap(&mut app, KeyCode::KeyI);
+    assert!(move_up(&app).contains(&Binding::Key(KeyCode::KeyI)));
+
+    // and Enter would pick the row again and start over, binding J instead
+    tap(&mut app, KeyCode::Enter);
+    tap(&mut app, KeyCode::Enter);
+    tap(&mut app, KeyCode::KeyJ);
+    let bindings = move_up(&app);
+    assert!(bindings.contains(&Binding::Key(KeyCode::Enter)));
+    assert!(!bindings.contains(&Binding::Key(KeyCode::KeyJ)));
+}
@@ -0,0 +1,534 @@
+use std::time::Duration;
+
+use arcade_core::testing::TestApp;
+use arcade_core::{
+    Arena, Ball, BallHitPaddle, Collider, GameConfig, GameState, GoalScored, Hud, HudRegion,
+    Interpolated, MainCamera, MatchEnded, Paddle, PaddleInput, PaddleInputs, Player, Player1,
+    Player2, Replay, ReplayMode, Score, ScoreText, Side, Velocity, Wall,
+};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowCreated, WindowResized, WindowResolution};
+use pong::ai::{Controller, Difficulty};
+use pong::rules::MatchRules;
+use pong::serve::{Rally, RallyText, Serve, SERVE_DELAY};
+use pong::PongPlugin;
+
+const ARENA: Vec2 = Vec2::new(800., 400.);
+
+fn pong(rules: MatchRules) -> TestApp {
+    let mut app = TestApp::new(PongPlugin {
+        rules,
+        arena_size: Some(ARENA),
+        ..Default::default()
+    });
+    // serve straight along the middle, into the paddles' way
+    app.world_mut().resource_mut::<GameConfig>().max_serve_angle = 0.;
+    app.start_match();
+    app
+}
+
+/// Paddles held against opposite walls, so neither is in the way of the
+/// ball served from the other.
+fn paddles_out_of_the_way(app: &mut TestApp) {
+    app.hold_inputs(PaddleInputs {
+        player1: PaddleInput::UP,
+        player2: PaddleInput::DOWN,
+    });
+}
+
+#[test]
+fn spawns_the_arena_ball_and_paddles() {
+    let mut app = pong(MatchRules::default());
+    assert_eq!(app.count::<With<Wall>>(), 4);
+    assert_eq!(app.count::<With<Ball>>(), 1);
+    assert_eq!(app.count::<With<Paddle>>(), 2);
+    assert_eq!(app.world().resource::<Arena>().width, ARENA.x);
+    assert_eq!(app.state(), GameState::Playing);
+}
+
+#[test]
+fn paddles_stop_at_the_ceiling() {
+    let mut app = pong(MatchRules::default());
+    paddles_out_of_the_way(&mut app);
+    app.run_ticks(600);
+
+    let mut paddles = app
+        .world_mut()
+        .query_filtered::<&Transform, (With<Paddle>, With<Player1>)>();
+    let paddle = paddles.single(app.world());
+    let paddle_height = app.world().resource::<GameConfig>().paddle_height;
+    assert_eq!(paddle.translation.y, ARENA.y / 2. - paddle_height / 2.);
+}
+
+/// Where the simulation has player 1's paddle, rather than where it was
+/// last drawn.
+fn player1_paddle_y(app: &mut TestApp) -> f32 {
+    let mut paddles = app
+        .world_mut()
+        .query_filtered::<&Interpolated, (With<Paddle>, With<Player1>)>();
+    paddles.single(app.world()).simulated().y
+}
+
+#[test]
+fn a_pointer_steers_the_paddle_no_faster_than_the_limit() {
+    let mut app = pong(MatchRules::default());
+    app.hold_inputs(PaddleInputs {
+        player1: PaddleInput::towards(120.),
+        player2: PaddleInput::DOWN,
+    });
+    app.run_ticks(1);
+    let config = *app.world().resource::<GameConfig>();
+    let tick = app
+        .world()
+        .resource::<Time<Fixed>>()
+        .timestep()
+        .as_secs_f32();
+    let y = player1_paddle_y(&mut app);
+    assert!(y > 0. && y <= config.max_pointer_speed * tick + 1e-3);
+
+    app.run_ticks(120);
+    assert_eq!(player1_paddle_y(&mut app), 120.);
+}
+
+#[test]
+fn paddles_in_the_way_keep_the_rally_going() {
+    let mut app = pong(MatchRules::default());
+    app.run_ticks(2000);
+
+    assert_eq!(app.score().player1 + app.score().player2, 0);
+    let ball = app.ball_position();
+    assert!(ball.x.abs() < ARENA.x / 2. && ball.y.abs() < ARENA.y / 2.);
+}
+
+#[test]
+fn ball_past_a_paddle_scores_and_is_served_again() {
+    let mut app = pong(MatchRules::default());
+    paddles_out_of_the_way(&mut app);
+
+    let scored = app.run_until(1000, |world| {
+        let score = world.resource::<Score>();
+        score.player1 + score.player2 == 1
+    });
+    assert!(scored);
+    // the ball waits in front of the paddle of whoever lost the point
+    let waiting = app.ball_position();
+    assert!(waiting.x.abs() > ARENA.x / 4.);
+    app.run_ticks(10);
+    assert_eq!(app.ball_position(), waiting);
+
+    let served = app.run_until(1000, |world| world.resource::<Serve>().remaining.is_none());
+    assert!(served);
+    app.run_ticks(1);
+    assert_ne!(app.ball_position(), waiting);
+}
+
+/// The `E`s sent on the last tick or two, before they're cleared.
+fn sent<E: Event + Copy>(world: &World) -> Vec<E> {
+    let events = world.resource::<Events<E>>();
+    events.get_reader().read(events).copied().collect()
+}
+
+#[test]
+fn hits_and_goals_are_sent_as_events() {
+    let mut app = pong(MatchRules::default());
+    let config = *app.world().resource::<GameConfig>();
+    let hit = app.run_until(1000, |world| !sent::<BallHitPaddle>(world).is_empty());
+    assert!(hit);
+    let hit = sent::<BallHitPaddle>(app.world())[0];
+    assert!(app.world().get::<Paddle>(hit.paddle).is_some());
+    assert!(app.world().get::<Ball>(hit.ball).is_some());
+    // the paddle sends the ball back the way it came, a little faster
+    assert_eq!(hit.normal.y, 0.);
+    assert_eq!(hit.normal.x.signum(), -hit.point.x.signum());
+    assert!(hit.speed > config.ball_speed);
+
+    paddles_out_of_the_way(&mut app);
+    let scored = app.run_until(2000, |world| !sent::<GoalScored>(world).is_empty());
+    assert!(scored);
+    let goal = sent::<GoalScored>(app.world())[0];
+    let score = app.score();
+    let (scorer, side) = if score.player1 == 1 {
+        (Player::One, Side::Right)
+    } else {
+        (Player::Two, Side::Left)
+    };
+    assert_eq!(goal.scorer, Some(scorer));
+    assert_eq!(goal.side, side);
+}
+
+#[test]
+fn the_server_can_serve_before_the_countdown_ends() {
+    let mut app = pong(MatchRules::default());
+    paddles_out_of_the_way(&mut app);
+    app.run_until(1000, |world| {
+        let score = world.resource::<Score>();
+        score.player1 + score.player2 == 1
+    });
+    let remaining = app.world().resource::<Serve>().remaining.unwrap();
+    assert!(remaining > SERVE_DELAY / 2.);
+
+    let serve = PaddleInput {
+        serve: true,
+        ..PaddleInput::UP
+    };
+    app.hold_inputs(PaddleInputs {
+        player1: serve,
+        player2: PaddleInput {
+            serve: true,
+            ..PaddleInput::DOWN
+        },
+    });
+    app.run_ticks(1);
+    assert_eq!(app.world().resource::<Serve>().remaining, None);
+}
+
+fn ball_speed(app: &mut TestApp) -> f32 {
+    let mut balls = app.world_mut().query_filtered::<&Velocity, With<Ball>>();
+    balls.single(app.world()).speed
+}
+
+#[test]
+fn paddle_hits_speed_the_ball_up_until_the_next_serve() {
+    let mut app = pong(MatchRules::default());
+    let config = *app.world().resource::<GameConfig>();
+    app.run_ticks(1);
+    assert_eq!(ball_speed(&mut app), config.ball_speed);
+
+    app.run_ticks(1000);
+    let rally_speed = ball_speed(&mut app);
+    assert!(rally_speed > config.ball_speed);
+    assert!(rally_speed <= config.max_ball_speed);
+
+    paddles_out_of_the_way(&mut app);
+    app.run_until(1000, |world| world.resource::<Serve>().remaining.is_some());
+    app.run_until(1000, |world| world.resource::<Serve>().remaining.is_none());
+    assert_eq!(ball_speed(&mut app), config.ball_speed);
+}
+
+#[test]
+fn fast_ball_bounces_off_a_paddle_instead_of_tunnelling() {
+    let mut app = pong(MatchRules::default());
+    app.run_ticks(1);
+    // far enough in one tick to jump clean over a paddle
+    let mut balls = app
+        .world_mut()
+        .query_filtered::<&mut Velocity, With<Ball>>();
+    let mut velocity = balls.single_mut(app.world_mut());
+    velocity.speed = 6000.;
+    app.run_ticks(200);
+
+    assert_eq!(app.score().player1 + app.score().player2, 0);
+}
+
+#[test]
+fn match_ends_once_a_player_reaches_the_points() {
+    let mut app = pong(MatchRules {
+        points_to_win: 2,
+        win_by_two: false,
+        best_of: 1,
+        ..Default::default()
+    });
+    paddles_out_of_the_way(&mut app);
+
+    let over = app.run_until(5000, |world| !sent::<MatchEnded>(world).is_empty());
+    assert!(over);
+    let score = app.score();
+    assert_eq!(score.player1.max(score.player2), 2);
+    let winner = if score.player1 == 2 {
+        Player::One
+    } else {
+        Player::Two
+    };
+    assert_eq!(
+        sent::<MatchEnded>(app.world()),
+        [MatchEnded {
+            winner: Some(winner)
+        }]
+    );
+    app.run_ticks(1);
+    assert_eq!(app.state(), GameState::GameOver);
+}
+
+#[test]
+fn rules_that_cant_decide_a_match_are_rejected() {
+    assert!(MatchRules::default().validate().is_ok());
+    for (points_to_win, best_of) in [(0, 1), (11, 0), (11, 2)] {
+        let rules = MatchRules {
+            points_to_win,
+            best_of,
+            ..Default::default()
+        };
+        assert!(rules.validate().is_err(), "{rules:?} should be rejected");
+    }
+}
+
+#[test]
+fn match_rules_are_kept_in_a_replays_setup() {
+    let rules = MatchRules {
+        points_to_win: 5,
+        win_by_two: false,
+        best_of: 3,
+        ..Default::default()
+    };
+    assert_eq!(rules.to_setup(), "rules 5 1 3");
+    assert_eq!(MatchRules::from_setup(&rules.to_setup()), Some(rules));
+    for line in ["rules 0 2 1", "rules 11 3 1", "rules 11 2 4", "rules 11 2"] {
+        assert_eq!(MatchRules::from_setup(line), None, "{line}");
+    }
+}
+
+#[test]
+fn config_changes_resize_the_paddles_mid_match() {
+    let mut app = pong(MatchRules::default());
+    app.world_mut().resource_mut::<GameConfig>().paddle_height = 120.;
+    app.run_ticks(1);
+
+    let mut colliders = app.world_mut().query_filtered::<&Collider, With<Paddle>>();
+    for collider in colliders.iter(app.world()) {
+        assert_eq!(collider.size.y, 120.);
+    }
+}
+
+#[test]
+fn config_changes_keep_the_paddles_and_ball_inside_the_walls() {
+    let mut app = pong(MatchRules::default());
+    paddles_out_of_the_way(&mut app);
+    app.run_ticks(600);
+    // paused, so nothing else moves them
+    app.set_state(GameState::Paused);
+    app.run_ticks(1);
+    let mut balls = app
+        .world_mut()
+        .query_filtered::<&mut Transform, With<Ball>>();
+    balls.single_mut(app.world_mut()).translation.y = ARENA.y / 2. - 7.;
+    let mut config = app.world_mut().resource_mut::<GameConfig>();
+    config.paddle_height = 200.;
+    config.ball_radius = 20.;
+    app.run_ticks(1);
+
+    let mut paddles = app.world_mut().query_filtered::<&Transform, With<Paddle>>();
+    for paddle in paddles.iter(app.world()) {
+        assert_eq!(paddle.translation.y.abs(), ARENA.y / 2. - 100.);
+    }
+    let mut balls = app.world_mut().query_filtered::<&Transform, With<Ball>>();
+    assert_eq!(balls.single(app.world()).translation.y, ARENA.y / 2. - 20.);
+}
+
+#[test]
+fn config_changes_wait_for_the_end_of_a_recorded_match() {
+    let dir = std::env::temp_dir().join(format!("pong-config-lock-{}", std::process::id()));
+    let mut app = TestApp::new(PongPlugin {
+        replay: ReplayMode::Record(dir.clone()),
+        arena_size: Some(ARENA),
+        ..Default::default()
+    });
+    let loaded = app.wait_for(Duration::from_secs(10), |world| {
+        !world.resource::<Assets<GameConfig>>().is_empty()
+    });
+    assert!(loaded, "the config file never loaded");
+    app.start_match();
+    let before = *app.world().resource::<GameConfig>();
+
+    // as if the file had been edited
+    let world = app.world_mut();
+    let file = world
+        .resource::<AssetServer>()
+        .load::<GameConfig>("pong.config.ron");
+    let mut configs = world.resource_mut::<Assets<GameConfig>>();
+    configs.get_mut(&file).unwrap().paddle_height = 120.;
+    app.run_ticks(10);
+    assert_eq!(*app.world().resource::<GameConfig>(), before);
+
+    app.set_state(GameState::GameOver);
+    app.run_ticks(1);
+    assert_eq!(app.world().resource::<GameConfig>().paddle_height, 120.);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn the_same_seed_plays_the_same_match() {
+    let play = || {
+        let mut app = TestApp::new(PongPlugin {
+            player1: Controller::Computer(Difficulty::Hard),
+            player2: Controller::Computer(Difficulty::Easy),
+            arena_size: Some(ARENA),
+            seed: Some(7),
+            ..Default::default()
+        });
+        app.start_match();
+        app.run_ticks(3000);
+        (app.ball_position(), app.score())
+    };
+    assert_eq!(play(), play());
+}
+
+#[test]
+fn the_simulation_runs_at_the_tick_rate_given() {
+    let app = TestApp::new(PongPlugin {
+        tick_rate: Some(60.),
+        ..Default::default()
+    });
+    let timestep = app.world().resource::<Time<Fixed>>().timestep();
+    assert_eq!(timestep, Duration::from_secs_f64(1. / 60.));
+}
+
+#[test]
+fn resizing_the_window_letterboxes_the_same_arena() {
+    let mut app = pong(MatchRules::default());
+    let window = app
+        .world_mut()
+        .spawn((
+            Window {
+                resolution: WindowResolution::new(1000., 600.),
+                ..Default::default()
+            },
+            PrimaryWindow,
+        ))
+        .id();
+    app.world_mut().send_event(WindowCreated { window });
+    let viewport = |app: &mut TestApp| {
+        let mut cameras = app
+            .world_mut()
+            .query_filtered::<&Camera, With<MainCamera>>();
+        cameras.single(app.world()).viewport.clone().unwrap()
+    };
+
+    app.run_ticks(1);
+    let wide = viewport(&mut app);
+    let mut windows = app.world_mut().query::<&mut Window>();
+    windows
+        .single_mut(app.world_mut())
+        .resolution
+        .set(1000., 1000.);
+    // the view is only refitted once the window says it was resized
+    app.run_ticks(1);
+    assert_eq!(viewport(&mut app).physical_size, wide.physical_size);
+    assert_eq!(viewport(&mut app).physical_position, wide.physical_position);
+    app.world_mut().send_event(WindowResized {
+        window,
+        width: 1000.,
+        height: 1000.,
+    });
+    app.run_ticks(1);
+    let square = viewport(&mut app);
+
+    // the same view, scaled to the window's width and centred in its height
+    assert_eq!(square.physical_size, wide.physical_size);
+    assert_eq!(square.physical_size.x, 1000);
+    let bars = 1000 - square.physical_size.y;
+    assert_eq!(square.physical_position, UVec2::new(0, bars / 2));
+    assert_eq!(app.world().resource::<Arena>().width, ARENA.x);
+}
+
+#[test]
+fn the_hud_shows_the_scores_and_rally_around_the_arena() {
+    let mut app = pong(MatchRules::default());
+    app.run_ticks(1000);
+    let rally = app.world().resource::<Rally>().0;
+    assert!(rally > 0);
+
+    let hud = app.world().resource::<Hud>();
+    let regions = [
+        (hud.region(HudRegion::TopLeft), "0"),
+        (hud.region(HudRegion::TopRight), "0"),
+        (hud.region(HudRegion::Bottom), &*format!("Rally: {rally}")),
+    ];
+    let mut texts = app
+        .world_mut()
+        .query_filtered::<(&Parent, &Text), Or<(With<ScoreText>, With<RallyText>)>>();
+    let mut placed: Vec<_> = texts
+        .iter(app.world())
+        .map(|(parent, text)| (parent.get(), text.sections[0].value.as_str()))
+        .collect();
+    placed.sort();
+    let mut expected = regions.to_vec();
+    expected.sort();
+    assert_eq!(placed, expected);
+    assert_eq!(app.count::<(With<ScoreText>, With<Player2>)>(), 1);
+}
+
+/// Where the ball and paddles are and how fast the ball is going, and the
+/// score.
+type MatchState = (Vec2, Velocity, Vec<Vec3>, Score);
+
+fn match_state(app: &mut TestApp) -> MatchState {
+    let mut balls = app.world_mut().query_filtered::<&Velocity, With<Ball>>();
+    let velocity = *balls.single(app.world());
+    let mut paddles = app
+        .world_mut()
+        .query_filtered::<&Interpolated, With<Paddle>>();
+    let mut paddles: Vec<_> = paddles
+        .iter(app.world())
+        .map(Interpolated::simulated)
+        .collect();
+    paddles.sort_by(|a, b| a.x.total_cmp(&b.x));
+    (app.ball_position(), velocity, paddles, app.score())
+}
+
+/// Plays `ticks` of a match between computer players and returns the
+/// recording, along with how the match stood at the end.
+fn record(players: (Controller, Controller), ticks: u32) -> (Replay, MatchState) {
+    let dir = std::env::temp_dir().join(format!("pong-replay-{}-{ticks}", std::process::id()));
+    let mut app = TestApp::new(PongPlugin {
+        player1: players.0,
+        player2: players.1,
+        arena_size: Some(ARENA),
+        replay: ReplayMode::Record(dir.clone()),
+        ..Default::default()
+    });
+    app.start_match();
+    app.run_ticks(ticks);
+    let played = match_state(&mut app);
+    // ending the match saves the recording
+    app.set_state(GameState::GameOver);
+    let file = std::fs::read_dir(&dir)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let replay = Replay::load(&file, "pong").unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    (replay, played)
+}
+
+const COMPUTERS: (Controller, Controller) = (
+    Controller::Computer(Difficulty::Hard),
+    Controller::Computer(Difficulty::Easy),
+);
+
+#[test]
+fn a_recorded_match_plays_back_the_same() {
+    let (replay, played) = record(COMPUTERS, 3000);
+    assert!(played.2.iter().any(|paddle| paddle.y != 0.));
+
+    let mut app = TestApp::new(PongPlugin {
+        player1: COMPUTERS.0,
+        player2: COMPUTERS.1,
+        replay: ReplayMode::Playback(replay),
+        ..Default::default()
+    });
+    app.start_match();
+    app.run_ticks(3000);
+    assert_eq!(match_state(&mut app), played);
+}
+
+#[test]
+fn a_headless_replay_exits_once_its_inputs_run_out() {
+    let (replay, _) = record(COMPUTERS, 200);
+    let recorded = replay.inputs.len() as u64;
+
+    let mut app = TestApp::new(PongPlugin {
+        player1: COMPUTERS.0,
+        player2: COMPUTERS.1,
+        replay: ReplayMode::Playback(replay),
+        ..Default::default()
+    });
+    app.start_match();
+    let exited = app.run_until(1000, |world| {
+        !world.resource::<Events<AppExit>>().is_empty()
+    });
+    assert!(exited);
+    assert_eq!(app.tick(), recorded + 1);
+}
@@ -0,0 +1,154 @@
+use std::net::UdpSocket;
+
+use arcade_core::testing::TestApp;
+use arcade_core::{
+    Ball, GameRng, Hud, HudRegion, Interpolated, Paddle, PaddleInput, Player2, Score, ServeStarted,
+    Tick, Velocity,
+};
+use bevy::prelude::*;
+use pong::net::{NetConfig, NetSession, Packet, Seat};
+use pong::PongPlugin;
+
+#[test]
+fn packets_decode_to_what_was_encoded() {
+    let mut packet = Packet {
+        round: 3,
+        tick: 1200,
+        advantage: -2,
+        ack: 1190,
+        start: 1185,
+        inputs: vec![
+            PaddleInput::UP,
+            PaddleInput::moving(-0.25),
+            PaddleInput {
+                serve: true,
+                ..PaddleInput::towards(-120.)
+            },
+        ],
+        checksum: Some((1140, u64::MAX)),
+        seed: Some(42),
+    };
+    let bytes = packet.encode();
+    assert_eq!(Packet::decode(&bytes), Some(packet.clone()));
+
+    packet.checksum = None;
+    packet.seed = None;
+    packet.inputs.clear();
+    assert_eq!(Packet::decode(&packet.encode()), Some(packet));
+
+    // cut short, part way through an input, or not ours at all
+    assert_eq!(Packet::decode(&bytes[..20]), None);
+    assert_eq!(Packet::decode(&bytes[..bytes.len() - 1]), None);
+    let mut stray = bytes.clone();
+    stray[0] = b'X';
+    assert_eq!(Packet::decode(&stray), None);
+}
+
+/// Two peers set up to play each other over loopback.
+fn peers() -> (TestApp, TestApp) {
+    // find two free ports, then let go of them for the peers
+    let sockets = [(); 2].map(|_| UdpSocket::bind("127.0.0.1:0").unwrap());
+    let [a, b] = sockets
+        .each_ref()
+        .map(|socket| socket.local_addr().unwrap());
+    drop(sockets);
+
+    let peer = |seat, local, peer| {
+        TestApp::new(PongPlugin {
+            online: Some(NetConfig { seat, local, peer }),
+            ..Default::default()
+        })
+    };
+    (peer(Seat::Player1, a, b), peer(Seat::Player2, b, a))
+}
+
+/// The simulated state the peers have to agree on.
+fn state(app: &mut TestApp) -> (u64, Vec<(Vec3, Velocity)>, Vec<Vec3>, Score) {
+    let world = app.world_mut();
+    let balls = world
+        .query_filtered::<(&Interpolated, &Velocity), With<Ball>>()
+        .iter(world)
+        .map(|(ball, velocity)| (ball.simulated(), *velocity))
+        .collect();
+    let mut paddles: Vec<_> = world
+        .query_filtered::<&Interpolated, With<Paddle>>()
+        .iter(world)
+        .map(Interpolated::simulated)
+        .collect();
+    paddles.sort_by(|a, b| a.x.total_cmp(&b.x));
+    (
+        world.resource::<Tick>().0,
+        balls,
+        paddles,
+        *world.resource::<Score>(),
+    )
+}
+
+fn player2_paddle_y(app: &mut TestApp) -> f32 {
+    let world = app.world_mut();
+    let mut paddles = world.query_filtered::<&Interpolated, With<Player2>>();
+    paddles.single(world).simulated().y
+}
+
+#[test]
+fn a_late_input_is_rolled_back_to_and_the_peers_agree() {
+    let (mut player1, mut player2) = peers();
+    player2
+        .world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::ArrowDown);
+
+    // player 2 starts once it's heard the seed player 1 picked
+    player1.start_match();
+    player2.start_match();
+    assert_eq!((player1.tick(), player2.tick()), (1, 1));
+    let seed = |app: &TestApp| app.world().resource::<GameRng>().seed();
+    assert_eq!(seed(&player1), seed(&player2));
+
+    // player 1 serves and runs ahead, guessing player 2 isn't moving
+    player1.run_ticks(3);
+    assert_eq!(player2_paddle_y(&mut player1), 0.);
+    // ...then hears they were all along, and plays everything from the serve
+    // on again
+    player2.run_ticks(3);
+    player1.run_ticks(1);
+    // without telling anyone about the serve a second time
+    assert!(sent::<ServeStarted>(player1.world()).is_empty());
+    player2.run_ticks(1);
+    let moved = player2_paddle_y(&mut player2);
+    assert!(moved < 0.);
+    assert_eq!(player2_paddle_y(&mut player1), moved);
+    assert_eq!(state(&mut player1), state(&mut player2));
+
+    for _ in 0..200 {
+        player1.run_ticks(1);
+        player2.run_ticks(1);
+    }
+    assert_eq!(state(&mut player1), state(&mut player2));
+    for app in [&player1, &player2] {
+        let session = app.world().resource::<NetSession>();
+        assert_eq!(session.desync(), None);
+        assert!(session.in_sync_to() >= 120);
+    }
+}
+
+#[test]
+fn the_connection_status_is_shown_below_the_arena() {
+    let (mut player1, _player2) = peers();
+    player1.run_ticks(1);
+    let world = player1.world_mut();
+    let region = world.resource::<Hud>().region(HudRegion::BottomRight);
+    let mut texts = world.query::<(&Parent, &Text)>();
+    let status: Vec<_> = texts
+        .iter(world)
+        .filter(|(parent, _)| parent.get() == region)
+        .map(|(_, text)| text.sections[0].value.clone())
+        .collect();
+    assert_eq!(status, ["Online, in sync to tick 0"]);
+}
+
+/// The `E`s sent on the last tick or two, before they're cleared.
+fn sent<E: Event + Copy>(world: &World) -> Vec<E> {
+    let events = world.resource::<Events<E>>();
+    events.get_reader().read(events).copied().collect()
+}